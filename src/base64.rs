@@ -0,0 +1,91 @@
+//! Binary data as base64 text, the `xs:base64Binary` convention, for use with
+//! `#[serde(with = "serde_xml_rs::base64")]` on a `Vec<u8>` field.
+//!
+//! `serde_xml_rs` writes all bytes (`serialize_bytes`) as base64 with the standard
+//! alphabet and padding, but a `Vec<u8>` is serialized as a sequence of numbers
+//! unless it uses this module. Bytes are deserialized as the element's text itself,
+//! so reading base64 back needs this module too. Whitespace in the text, as left by
+//! line wrapping, is ignored.
+
+use std::fmt;
+
+use serde::{de, Deserializer, Serializer};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn serialize<T: AsRef<[u8]>, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_bytes(value.as_ref())
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    deserializer.deserialize_str(Base64Visitor)
+}
+
+/// Encodes `bytes` as base64 with padding.
+pub fn encode(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (i, &byte)| group | u32::from(byte) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(group >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+/// Decodes base64 text with padding, ignoring whitespace, or returns `None` if it
+/// isn't valid base64.
+pub fn decode(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = text.bytes().filter(|byte| !byte.is_ascii_whitespace()).collect();
+    if !digits.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(digits.len() / 4 * 3);
+    for (index, quad) in digits.chunks(4).enumerate() {
+        let is_last = index == digits.len() / 4 - 1;
+        let padding = quad.iter().rev().take_while(|&&digit| digit == b'=').count();
+        if padding > 2 || (padding > 0 && !is_last) {
+            return None;
+        }
+
+        let mut group = 0u32;
+        for &digit in &quad[..4 - padding] {
+            let value = ALPHABET.iter().position(|&letter| letter == digit)?;
+            group = group << 6 | value as u32;
+        }
+        group <<= 6 * padding as u32;
+        bytes.extend_from_slice(&group.to_be_bytes()[1..4 - padding]);
+    }
+    Some(bytes)
+}
+
+struct Base64Visitor;
+
+impl<'de> de::Visitor<'de> for Base64Visitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("base64 encoded bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Vec<u8>, E> {
+        decode(value).ok_or_else(|| E::custom(format!("`{}` is not valid base64", value)))
+    }
+
+    // Binary formats hand over what `serialize_bytes` wrote as it is.
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(value.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(value)
+    }
+}
@@ -0,0 +1,21 @@
+//! Text content that has to be a CDATA section, for use with
+//! `#[serde(with = "serde_xml_rs::cdata")]`.
+//!
+//! Serializing always writes the string as a CDATA section. Deserializing rejects
+//! plain text when the deserializer was set up with `distinguish_cdata(true)`, and
+//! otherwise accepts either form, as the parser can't tell them apart.
+
+use serde::{Deserializer, Serializer};
+
+use token;
+
+/// Newtype struct name that asks `serde_xml_rs` for CDATA text.
+pub const TOKEN: &str = "$serde_xml_rs::cdata";
+
+pub fn serialize<T: AsRef<str>, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_newtype_struct(TOKEN, value.as_ref())
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    token::deserialize_token_string(deserializer, TOKEN, "a CDATA section")
+}
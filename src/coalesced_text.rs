@@ -0,0 +1,24 @@
+//! Reads the text of an element and all of its descendants into a `String` exactly as
+//! it appears, for use with `#[serde(with = "serde_xml_rs::coalesced_text")]`.
+//!
+//! Unlike `inner_text`, the text nodes are concatenated without anything in between,
+//! so text split into several nodes by CDATA sections, comments or a parser that
+//! doesn't coalesce characters is put back together as it was. This is meant for
+//! parsers that keep whitespace, as the default configuration trims each text node.
+//!
+//! Serializing writes the string back as plain text content.
+
+use serde::{Deserializer, Serializer};
+
+use token;
+
+/// Newtype struct name that asks `serde_xml_rs::Deserializer` for the coalesced text.
+pub const TOKEN: &str = "$serde_xml_rs::coalesced_text";
+
+pub fn serialize<T: AsRef<str>, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(value.as_ref())
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    token::deserialize_token_string(deserializer, TOKEN, "the text content of an element")
+}
@@ -0,0 +1,21 @@
+//! RFC 3339 text for `chrono::DateTime<Utc>` values, for use with
+//! `#[serde(with = "serde_xml_rs::datetime")]`.
+//!
+//! With the `chrono` feature enabled, plain `DateTime<Utc>` fields already serialize to
+//! RFC 3339 text through chrono's own serde support. This module additionally pins
+//! the output to the `Z` suffix with only as many fractional digits as needed, and
+//! accepts any UTC offset on input.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S: Serializer>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&text)
+        .map(|value| value.with_timezone(&Utc))
+        .map_err(de::Error::custom)
+}
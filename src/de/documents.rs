@@ -0,0 +1,66 @@
+use std::io::{self, BufReader, Read};
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+
+use de::from_reader;
+use error::Result;
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// An iterator over the documents in a stream where every document starts with a
+/// UTF-8 byte order mark, as created by `deserialize_iter`.
+pub struct Documents<R: Read, T> {
+    reader: BufReader<R>,
+    finished: bool,
+    marker: PhantomData<T>,
+}
+
+impl<R: Read, T> Documents<R, T> {
+    pub fn new(reader: R) -> Self {
+        Documents {
+            reader: BufReader::new(reader),
+            finished: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// Reads up to the next byte order mark, or to the end of the stream.
+    fn read_document(&mut self) -> io::Result<Vec<u8>> {
+        let mut document = Vec::new();
+        let mut byte = [0];
+        loop {
+            if self.reader.read(&mut byte)? == 0 {
+                self.finished = true;
+                return Ok(document);
+            }
+            document.push(byte[0]);
+            if document.ends_with(BOM) {
+                document.truncate(document.len() - BOM.len());
+                return Ok(document);
+            }
+        }
+    }
+}
+
+impl<R: Read, T: DeserializeOwned> Iterator for Documents<R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        while !self.finished {
+            let document = match self.read_document() {
+                Ok(document) => document,
+                Err(error) => {
+                    self.finished = true;
+                    return Some(Err(error.into()));
+                },
+            };
+            // Only whitespace can come before the first BOM or between a document
+            // and the next BOM without being part of a document.
+            if !document.iter().all(u8::is_ascii_whitespace) {
+                return Some(from_reader(document.as_slice()));
+            }
+        }
+        None
+    }
+}
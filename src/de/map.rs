@@ -1,17 +1,32 @@
+use std::borrow::Cow;
 use std::io::Read;
 
 use serde::de::{self, IntoDeserializer};
 use xml::attribute::OwnedAttribute;
 use xml::reader::XmlEvent;
 
+use de::{BoolTokens, KeySource};
 use Deserializer;
 use error::{Error, Result};
 
 pub struct MapAccess<'a, R: 'a + Read> {
     attrs: ::std::vec::IntoIter<OwnedAttribute>,
     next_value: Option<String>,
+    /// Processing instructions to yield as the value of the `$pi` key.
+    next_processing_instructions: Option<Vec<Vec<String>>>,
     de: &'a mut Deserializer<R>,
     inner_value: bool,
+    /// Attribute names seen so far that may also appear as a child element.
+    either_source_attrs: Vec<String>,
+    namespace: Option<String>,
+    /// Number of processing instructions that were already pending when the element
+    /// started, if its processing instructions are captured.
+    processing_instructions_start: Option<usize>,
+    /// The struct's fields, if the ones found are recorded, unknown ones rejected or
+    /// keys matched to them regardless of case.
+    fields: Option<&'static [&'static str]>,
+    strict: bool,
+    ignore_case: bool,
 }
 
 impl<'a, R: 'a + Read> MapAccess<'a, R> {
@@ -19,10 +34,150 @@ impl<'a, R: 'a + Read> MapAccess<'a, R> {
         MapAccess {
             attrs: attrs.into_iter(),
             next_value: None,
-            de: de,
-            inner_value: inner_value,
+            next_processing_instructions: None,
+            de,
+            inner_value,
+            either_source_attrs: Vec::new(),
+            namespace: None,
+            processing_instructions_start: None,
+            fields: None,
+            strict: false,
+            ignore_case: false,
         }
     }
+
+    /// Record which of `fields` are found into the innermost entry of
+    /// `Deserializer::populated_fields`.
+    pub fn record_populated_fields(&mut self, fields: &'static [&'static str]) {
+        self.fields = Some(fields);
+    }
+
+    /// Fail on child elements that don't match any of `fields`.
+    pub fn deny_unknown_elements(&mut self, fields: &'static [&'static str]) {
+        self.fields = Some(fields);
+        self.strict = true;
+    }
+
+    /// Match keys to `fields` regardless of ASCII case.
+    pub fn ignore_case(&mut self, fields: &'static [&'static str]) {
+        self.fields = Some(fields);
+        self.ignore_case = true;
+    }
+
+    /// The field `key` is matched to, which is `key` itself unless case is ignored and
+    /// a field differs from it only in case.
+    fn field_key(&self, key: String) -> String {
+        match self.fields {
+            Some(fields) if self.ignore_case && !fields.contains(&key.as_str()) => fields
+                .iter()
+                .find(|field| field.eq_ignore_ascii_case(&key))
+                .map_or(key, |field| field.to_string()),
+            _ => key,
+        }
+    }
+
+    fn populate(&mut self, key: &str) {
+        if !self.fields.is_some_and(|fields| fields.contains(&key)) {
+            return;
+        }
+        if let Some(populated) = self.de.populated_fields.last_mut() {
+            if !populated.iter().any(|field| field == key) {
+                populated.push(key.to_string());
+            }
+        }
+    }
+
+    /// Yield `namespace` as the value of a `$namespace` key before anything else.
+    pub fn capture_namespace(&mut self, namespace: Option<String>) {
+        self.namespace = namespace;
+    }
+
+    /// Yield the processing instructions read inside the element as the value of a
+    /// `$pi` key, once its children have been read.
+    pub fn capture_processing_instructions(&mut self) {
+        self.processing_instructions_start = Some(self.de.processing_instructions.len());
+    }
+
+    fn next_processing_instructions_key_seed<'de, K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>> {
+        let start = self.processing_instructions_start.take().unwrap_or_default();
+        let captured = self.de.processing_instructions.split_off(start);
+        self.de.last_key_source = Some(KeySource::ProcessingInstructions);
+        self.populate("$pi");
+        self.next_processing_instructions = Some(
+            captured
+                .into_iter()
+                .map(|(target, data)| vec![target, data])
+                .collect(),
+        );
+        seed.deserialize("$pi".into_deserializer()).map(Some)
+    }
+
+    fn next_element_key_seed<'de, K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>> {
+        let source = match *self.de.peek()? {
+            XmlEvent::StartElement { .. } => KeySource::Element,
+            XmlEvent::Characters(_) => KeySource::Text,
+            _ => return Ok(None),
+        };
+        self.de.last_key_source = Some(source);
+        let element_keys = self.de.element_keys;
+        if let Some(fields) = self.fields {
+            let key = match *self.de.peek()? {
+                XmlEvent::StartElement { ref name, .. } if !self.inner_value => {
+                    element_keys.name_key(name).into_owned()
+                },
+                _ => "$value".to_string(),
+            };
+            let key = self.field_key(key);
+            if self.strict && source == KeySource::Element && !fields.contains(&key.as_str()) {
+                return Err(de::Error::unknown_field(&key, fields));
+            }
+            self.populate(&key);
+            return seed.deserialize(key.into_deserializer()).map(Some);
+        }
+        match *self.de.peek()? {
+            XmlEvent::StartElement { ref name, .. } => seed.deserialize(
+                if !self.inner_value {
+                    element_keys.name_key(name)
+                } else {
+                    Cow::Borrowed("$value")
+                }.into_deserializer(),
+            ).map(Some),
+            _ => seed.deserialize("$value".into_deserializer()).map(Some),
+        }
+    }
+
+    fn skip_empty_elements(&mut self) -> Result<()> {
+        if self.inner_value || !self.de.empty_as_default {
+            return Ok(());
+        }
+        while self.de.next_is_empty_element()? {
+            self.de.next()?;
+            self.de.next()?;
+        }
+        Ok(())
+    }
+
+    fn skip_elements_shadowed_by_attrs(&mut self) -> Result<()> {
+        while !self.either_source_attrs.is_empty() {
+            let shadowed = match *self.de.peek()? {
+                XmlEvent::StartElement { ref name, .. } => {
+                    self.either_source_attrs.contains(&name.local_name)
+                },
+                _ => false,
+            };
+            if !shadowed {
+                break;
+            }
+            de::Deserializer::deserialize_ignored_any(&mut *self.de, de::IgnoredAny)?;
+        }
+        Ok(())
+    }
 }
 
 impl<'de, 'a, R: 'a + Read> de::MapAccess<'de> for MapAccess<'a, R> {
@@ -30,37 +185,62 @@ impl<'de, 'a, R: 'a + Read> de::MapAccess<'de> for MapAccess<'a, R> {
 
     fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
         debug_assert_eq!(self.next_value, None);
+        if let Some(namespace) = self.namespace.take() {
+            self.next_value = Some(namespace);
+            self.de.last_key_source = Some(KeySource::Namespace);
+            self.populate("$namespace");
+            return seed.deserialize("$namespace".into_deserializer()).map(Some);
+        }
         match self.attrs.next() {
             Some(OwnedAttribute { name, value }) => {
                 self.next_value = Some(value);
-                seed.deserialize(name.local_name.into_deserializer())
+                self.de.last_key_source = Some(KeySource::Attribute);
+                if self.de.either_source_fields.contains(&name.local_name) {
+                    self.either_source_attrs.push(name.local_name.clone());
+                }
+                let name = self.de.element_keys.attribute_key(name);
+                let key = match self.de.attribute_prefix {
+                    Some(ref prefix) => format!("{}{}", prefix, name),
+                    None => name,
+                };
+                let key = self.field_key(key);
+                self.populate(&key);
+                seed.deserialize(key.into_deserializer())
                     .map(Some)
             },
-            None => match *self.de.peek()? {
-                XmlEvent::StartElement { ref name, .. } => seed.deserialize(
-                    if !self.inner_value {
-                        name.local_name.as_str()
-                    } else {
-                        "$value"
-                    }.into_deserializer(),
-                ).map(Some),
-                XmlEvent::Characters(_) => seed.deserialize("$value".into_deserializer()).map(Some),
-                _ => Ok(None),
+            None => {
+                self.skip_elements_shadowed_by_attrs()?;
+                self.skip_empty_elements()?;
+                if self.processing_instructions_start.is_some() {
+                    if let XmlEvent::EndElement { .. } = *self.de.peek()? {
+                        return self.next_processing_instructions_key_seed(seed);
+                    }
+                }
+                self.next_element_key_seed(seed)
             },
         }
     }
 
     fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        if let Some(processing_instructions) = self.next_processing_instructions.take() {
+            return seed.deserialize(processing_instructions.into_deserializer());
+        }
         match self.next_value.take() {
-            Some(value) => seed.deserialize(AttrValueDeserializer(value)),
+            Some(value) => seed.deserialize(
+                AttrValueDeserializer::new(value)
+                    .seq_of_one(self.de.singular_attribute_seqs)
+                    .bool_tokens(self.de.bool_tokens),
+            ),
             None => {
                 if !self.inner_value {
                     if let XmlEvent::StartElement { .. } = *self.de.peek()? {
                         self.de.set_map_value();
                     }
                 }
-                let result = seed.deserialize(&mut *self.de)?;
-                Ok(result)
+                self.de.set_mixed_content(self.inner_value);
+                let result = seed.deserialize(&mut *self.de);
+                self.de.set_mixed_content(false);
+                result
             },
         }
     }
@@ -70,12 +250,39 @@ impl<'de, 'a, R: 'a + Read> de::MapAccess<'de> for MapAccess<'a, R> {
     }
 }
 
-struct AttrValueDeserializer(String);
+pub struct AttrValueDeserializer {
+    value: String,
+    seq_of_one: bool,
+    bool_tokens: BoolTokens,
+}
+
+impl AttrValueDeserializer {
+    pub fn new(value: String) -> Self {
+        AttrValueDeserializer {
+            value,
+            seq_of_one: false,
+            bool_tokens: BoolTokens::default(),
+        }
+    }
+
+    /// Read booleans with the tokens the deserializer was configured with.
+    pub fn bool_tokens(mut self, tokens: BoolTokens) -> Self {
+        self.bool_tokens = tokens;
+        self
+    }
+
+    /// Deserialize a sequence as a single element holding the whole value, instead of
+    /// failing.
+    pub fn seq_of_one(mut self, enabled: bool) -> Self {
+        self.seq_of_one = enabled;
+        self
+    }
+}
 
 macro_rules! deserialize_type_attr {
     ($deserialize:ident => $visit:ident) => {
         fn $deserialize<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-            visitor.$visit(self.0.parse()?)
+            visitor.$visit(self.value.parse()?)
         }
     }
 }
@@ -84,17 +291,19 @@ impl<'de> de::Deserializer<'de> for AttrValueDeserializer {
     type Error = Error;
 
     fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_string(self.0)
+        visitor.visit_string(self.value)
     }
 
     deserialize_type_attr!(deserialize_i8 => visit_i8);
     deserialize_type_attr!(deserialize_i16 => visit_i16);
     deserialize_type_attr!(deserialize_i32 => visit_i32);
     deserialize_type_attr!(deserialize_i64 => visit_i64);
+    deserialize_type_attr!(deserialize_i128 => visit_i128);
     deserialize_type_attr!(deserialize_u8 => visit_u8);
     deserialize_type_attr!(deserialize_u16 => visit_u16);
     deserialize_type_attr!(deserialize_u32 => visit_u32);
     deserialize_type_attr!(deserialize_u64 => visit_u64);
+    deserialize_type_attr!(deserialize_u128 => visit_u128);
     deserialize_type_attr!(deserialize_f32 => visit_f32);
     deserialize_type_attr!(deserialize_f64 => visit_f64);
 
@@ -104,7 +313,7 @@ impl<'de> de::Deserializer<'de> for AttrValueDeserializer {
         _variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
-        visitor.visit_enum(self.0.into_deserializer())
+        visitor.visit_enum(self.value.into_deserializer())
     }
 
     fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
@@ -112,11 +321,40 @@ impl<'de> de::Deserializer<'de> for AttrValueDeserializer {
     }
 
     fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_bool(!self.0.is_empty())
+        visitor.visit_bool(self.bool_tokens.parse(&self.value)?)
+    }
+
+    fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.seq_of_one {
+            let value = AttrValueDeserializer::new(self.value).bool_tokens(self.bool_tokens);
+            visitor.visit_seq(SingleValueAccess(Some(value)))
+        } else {
+            self.deserialize_any(visitor)
+        }
     }
 
     forward_to_deserialize_any! {
-        char str string unit seq bytes map unit_struct newtype_struct tuple_struct
+        char str string unit bytes map unit_struct newtype_struct tuple_struct
         struct identifier tuple ignored_any byte_buf
     }
 }
+
+struct SingleValueAccess(Option<AttrValueDeserializer>);
+
+impl<'de> de::SeqAccess<'de> for SingleValueAccess {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>> {
+        match self.0.take() {
+            Some(value) => seed.deserialize(value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.iter().count())
+    }
+}
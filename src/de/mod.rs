@@ -1,16 +1,33 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
 use std::io::Read;
 
 use serde::de;
+use xml::common::{Position, TextPosition};
 use xml::reader::{EventReader, ParserConfig, XmlEvent};
+use xml::attribute::OwnedAttribute;
+use xml::escape::{escape_str_attribute, escape_str_pcdata};
 use xml::name::OwnedName;
+use xml::namespace::Namespace;
 
-use error::{Error, ErrorKind, Result};
+use error::{self, Error, ErrorKind, Result};
+use cdata;
+use coalesced_text;
+use inner_text;
+use raw;
+use XSI_NAMESPACE;
 use self::map::MapAccess;
-use self::seq::SeqAccess;
+use self::seq::{AttrTupleAccess, SeqAccess};
 use self::var::EnumAccess;
 
+pub use self::documents::Documents;
+pub use self::transcode::Transcoder;
+
+mod documents;
 mod map;
 mod seq;
+mod transcode;
 mod var;
 
 /// A convenience method for deserialize some object from a string.
@@ -39,6 +56,9 @@ pub fn from_str<'de, T: de::Deserialize<'de>>(s: &str) -> Result<T> {
 
 /// A convenience method for deserialize some object from a reader.
 ///
+/// UTF-16 input with a byte order mark and ISO-8859-1 input with a matching XML
+/// declaration are transcoded to UTF-8 first (see `Transcoder`).
+///
 /// ```rust
 /// # #[macro_use]
 /// # extern crate serde_derive;
@@ -57,40 +77,615 @@ pub fn from_str<'de, T: de::Deserialize<'de>>(s: &str) -> Result<T> {
 /// # }
 /// ```
 pub fn from_reader<'de, R: Read, T: de::Deserialize<'de>>(reader: R) -> Result<T> {
-    T::deserialize(&mut Deserializer::new_from_reader(reader))
+    let mut de = Deserializer::new_from_reader(Transcoder::new(reader));
+    T::deserialize(&mut de)
+}
+
+/// Deserialize each document of a stream in which every document starts with a UTF-8
+/// byte order mark, as produced by concatenating such files.
+///
+/// Every byte order mark in the stream is taken to start a new document.
+///
+/// ```rust
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// # extern crate serde;
+/// # extern crate serde_xml_rs;
+/// # use serde_xml_rs::deserialize_iter;
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Item {
+///     name: String,
+/// }
+/// # fn main() {
+/// let s = "\u{feff}<item name=\"a\"/>\n\u{feff}<item name=\"b\"/>";
+/// let items: Vec<Item> = deserialize_iter(s.as_bytes()).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(items, vec![Item { name: "a".to_string() }, Item { name: "b".to_string() }]);
+/// # }
+/// ```
+pub fn deserialize_iter<R: Read, T: de::DeserializeOwned>(reader: R) -> Documents<R, T> {
+    Documents::new(reader)
+}
+
+/// The outcome of `deserialize_if_complete`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Completion<T> {
+    /// The input held a whole document.
+    Complete(T),
+    /// The input stopped before the end of the document.
+    NeedsMoreInput,
+}
+
+/// Try a complete parse of a document that may not have arrived in full yet, like a
+/// buffer that is still being filled from the network.
+///
+/// Running out of input, `ErrorKind::UnexpectedEof`, is reported as
+/// `Completion::NeedsMoreInput` rather than as an error. Any other error is returned
+/// as usual, as it can't be fixed by more input. Nothing is kept from an attempt that
+/// needs more input, not even the values read so far, so the next call parses the
+/// whole buffer again from the start. There is no partially built `T` to return in
+/// that case either: a `Deserialize` impl only produces its value once every field
+/// has been read, and drops what it had when reading fails.
+///
+/// ```rust
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// # extern crate serde;
+/// # extern crate serde_xml_rs;
+/// # use serde_xml_rs::{deserialize_if_complete, Completion};
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Item {
+///     name: String,
+/// }
+/// # fn main() {
+/// let mut buffer = b"<item><name>hel".to_vec();
+/// assert_eq!(deserialize_if_complete::<Item>(&buffer).unwrap(), Completion::NeedsMoreInput);
+///
+/// buffer.extend_from_slice(b"lo</name></item>");
+/// let item = Item { name: "hello".to_string() };
+/// assert_eq!(deserialize_if_complete::<Item>(&buffer).unwrap(), Completion::Complete(item));
+/// # }
+/// ```
+pub fn deserialize_if_complete<'de, T: de::Deserialize<'de>>(
+    input: &[u8],
+) -> Result<Completion<T>> {
+    match from_reader(input) {
+        Ok(value) => Ok(Completion::Complete(value)),
+        Err(Error(ErrorKind::UnexpectedEof(_), _)) => Ok(Completion::NeedsMoreInput),
+        Err(error) => Err(error),
+    }
+}
+
+/// Turns an error from the XML reader into an `UnexpectedEof` if the input ran out,
+/// or a `Syntax` error otherwise. xml-rs reports most of the former as syntax errors
+/// that can only be told apart by their message, so this is the one place that reads
+/// it.
+fn reader_error(error: ::xml::reader::Error) -> Error {
+    use xml::reader::ErrorKind as XmlErrorKind;
+
+    let end_of_input = match *error.kind() {
+        XmlErrorKind::UnexpectedEof => true,
+        XmlErrorKind::Syntax(ref message) => message.starts_with("Unexpected end of stream"),
+        _ => false,
+    };
+    if end_of_input {
+        ErrorKind::UnexpectedEof(error.position()).into()
+    } else {
+        ErrorKind::Syntax(error).into()
+    }
+}
+
+type NameMap = dyn Fn(&str) -> Cow<str>;
+type StartElementHook = dyn FnMut(&OwnedName, &[OwnedAttribute]);
+
+/// Where the key of a struct field or map entry was read from, as reported by
+/// `Deserializer::last_key_source`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeySource {
+    /// An attribute of the element.
+    Attribute,
+    /// A child element.
+    Element,
+    /// Text content, for a `$value` key.
+    Text,
+    /// The element's namespace, for a `$namespace` key.
+    Namespace,
+    /// The processing instructions inside the element, for a `$pi` key.
+    ProcessingInstructions,
 }
 
 pub struct Deserializer<R: Read> {
     depth: usize,
     reader: EventReader<R>,
+    /// The configuration `reader` was created with, if it was created here.
+    config: Option<ParserConfig>,
     peeked: Option<XmlEvent>,
+    /// The event after `peeked`, when it had to be looked at too.
+    lookahead: Option<XmlEvent>,
     is_map_value: bool,
+    /// Whether the element the map value is read from is a newtype variant's, so that
+    /// a sequence is read from its children rather than from repeated elements.
+    newtype_variant: bool,
+    /// Whether the value being deserialized is the whole content of an element with a
+    /// `$value` field, which may mix text and child elements.
+    mixed_content: bool,
+    bool_tokens: BoolTokens,
+    ignore_namespaces: bool,
+    name_map: Option<Box<NameMap>>,
+    start_element_hook: Option<Box<StartElementHook>>,
+    strip_text_bom: bool,
+    either_source_fields: Vec<String>,
+    attribute_prefix: Option<String>,
+    allowed_roots: Vec<String>,
+    max_text_bytes: Option<usize>,
+    max_attributes: Option<usize>,
+    text_bytes: usize,
+    aliases: HashMap<String, String>,
+    expose_attributes: bool,
+    singular_attribute_seqs: bool,
+    current_attributes: Vec<OwnedAttribute>,
+    /// The resolved `xml:base` in scope, with the depth of the element that set it.
+    bases: Vec<(usize, String)>,
+    capture_processing_instructions: bool,
+    /// Processing instructions read but not yet handed to a `$pi` field, as target and
+    /// data.
+    processing_instructions: Vec<(String, String)>,
+    empty_as_default: bool,
+    element_keys: ElementKeys,
+    distinguish_cdata: bool,
+    /// For each text event read from `reader` but not yet consumed, whether it was a
+    /// CDATA section.
+    cdata_flags: VecDeque<bool>,
+    /// Whether the text consumed last came entirely from CDATA sections.
+    text_is_cdata: bool,
+    /// Whether text is trimmed here rather than by `reader`, so that it's known
+    /// which text nodes had whitespace around them.
+    trim_text: bool,
+    /// For each text event read from `reader` but not yet consumed, whether
+    /// whitespace was trimmed from its start and from its end.
+    trimmed_edges: VecDeque<(bool, bool)>,
+    /// Whether whitespace was trimmed from the start and end of the text consumed last.
+    text_edges: (bool, bool),
+    /// For each event read from `reader` but not yet consumed, its position as reported
+    /// by `reader`.
+    event_positions: VecDeque<TextPosition>,
+    /// The position of the event consumed last.
+    position: TextPosition,
+    last_key_source: Option<KeySource>,
+    strict: bool,
+    ignore_case: bool,
+    check_root_name: bool,
+    /// Whether the input is a fragment whose top level hasn't been started on yet.
+    fragment: bool,
+    record_populated_fields: bool,
+    /// The fields found so far for each struct being deserialized, innermost last.
+    populated_fields: Vec<Vec<String>>,
+    last_populated_fields: Vec<String>,
 }
 
-impl<'de, R: Read> Deserializer<R> {
+impl<R: Read> Deserializer<R> {
     pub fn new(reader: EventReader<R>) -> Self {
         Deserializer {
             depth: 0,
-            reader: reader,
+            reader,
+            config: None,
             peeked: None,
+            lookahead: None,
             is_map_value: false,
+            newtype_variant: false,
+            mixed_content: false,
+            bool_tokens: BoolTokens::default(),
+            ignore_namespaces: false,
+            name_map: None,
+            start_element_hook: None,
+            strip_text_bom: false,
+            either_source_fields: Vec::new(),
+            attribute_prefix: None,
+            allowed_roots: Vec::new(),
+            max_text_bytes: None,
+            max_attributes: None,
+            text_bytes: 0,
+            aliases: HashMap::new(),
+            expose_attributes: false,
+            singular_attribute_seqs: false,
+            current_attributes: Vec::new(),
+            bases: Vec::new(),
+            capture_processing_instructions: false,
+            processing_instructions: Vec::new(),
+            empty_as_default: false,
+            element_keys: ElementKeys::default(),
+            distinguish_cdata: false,
+            cdata_flags: VecDeque::new(),
+            text_is_cdata: false,
+            trim_text: false,
+            trimmed_edges: VecDeque::new(),
+            text_edges: (false, false),
+            event_positions: VecDeque::new(),
+            position: TextPosition::new(),
+            last_key_source: None,
+            strict: false,
+            ignore_case: false,
+            check_root_name: false,
+            fragment: false,
+            record_populated_fields: false,
+            populated_fields: Vec::new(),
+            last_populated_fields: Vec::new(),
         }
     }
 
     pub fn new_from_reader(reader: R) -> Self {
-        let config = ParserConfig::new()
+        Self::new_with_config(reader, Self::default_config())
+    }
+
+    /// Like `new_from_reader`, but resolves entities other than the five predefined
+    /// ones (for example those declared in a DTD) against `entities`.
+    pub fn new_from_reader_with_entities(reader: R, entities: HashMap<String, String>) -> Self {
+        let mut config = Self::default_config();
+        config.extra_entities = entities;
+
+        Self::new_with_config(reader, config)
+    }
+
+    fn new_with_config(reader: R, config: ParserConfig) -> Self {
+        let mut de = Self::new(EventReader::new_with_config(reader, reader_config(&config)));
+        de.trim_text = config.trim_whitespace;
+        de.config = Some(config);
+        de
+    }
+
+    /// Also accept the capitalized `True` and `False` tokens (as emitted by Python)
+    /// when deserializing booleans from element text or attribute values.
+    pub fn capitalized_bools(mut self, enabled: bool) -> Self {
+        self.bool_tokens.capitalized = enabled;
+        self
+    }
+
+    /// Accept exactly the XML Schema boolean tokens `true`, `false`, `1` and `0` for
+    /// `bool` text and attribute values, and reject anything else, including the `yes` and `no` that are
+    /// accepted by default. This takes precedence over
+    /// `capitalized_bools`.
+    pub fn xsd_bools(mut self, enabled: bool) -> Self {
+        self.bool_tokens.xsd = enabled;
+        self
+    }
+
+    /// Strip namespace prefixes and URIs from all element and attribute names, so
+    /// that everything is matched purely on local names.
+    pub fn ignore_namespaces(mut self, enabled: bool) -> Self {
+        self.ignore_namespaces = enabled;
+        self
+    }
+
+    /// Match elements and attributes that are in a namespace to fields on their
+    /// fully qualified `{namespace}local` name, so one document can fill both
+    /// `#[serde(rename = "{http://a.example}id")]` and `{http://b.example}id` fields.
+    /// Names outside of any namespace are still matched on their local name alone.
+    /// By default only the local name is used, regardless of namespace.
+    pub fn qualified_names(mut self, enabled: bool) -> Self {
+        self.element_keys.qualified_names = enabled;
+        self
+    }
+
+    /// Rewrite the local name of every element and attribute with `name_map` before it
+    /// is matched against field names, e.g. to map legacy names onto current ones.
+    pub fn with_name_map<F>(mut self, name_map: F) -> Self
+    where
+        F: Fn(&str) -> Cow<str> + 'static,
+    {
+        self.name_map = Some(Box::new(name_map));
+        self
+    }
+
+    /// Call `hook` with the name and attributes of each element as it is entered, e.g.
+    /// to build an index or report progress. This doesn't affect what is deserialized.
+    pub fn on_start_element<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(&OwnedName, &[OwnedAttribute]) + 'static,
+    {
+        self.start_element_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Remove a byte order mark (U+FEFF) from the start of text content, as can be left
+    /// behind where documents were concatenated.
+    pub fn strip_text_bom(mut self, enabled: bool) -> Self {
+        self.strip_text_bom = enabled;
+        self
+    }
+
+    /// Let each of `fields` be filled from either an attribute or a child element of
+    /// that name. If an element has both, the attribute wins and the child element is
+    /// skipped instead of being reported as a duplicate field.
+    pub fn either_source(mut self, fields: &[&str]) -> Self {
+        self.either_source_fields
+            .extend(fields.iter().map(|field| field.to_string()));
+        self
+    }
+
+    /// Match attributes to fields on their name with `prefix` in front, so that they
+    /// only fill fields renamed accordingly and plain field names only match child
+    /// elements. With the `@` prefix that the serializer writes attribute fields
+    /// with, an element and an attribute of the same name no longer collide:
+    ///
+    /// ```rust
+    /// # #[macro_use]
+    /// # extern crate serde_derive;
+    /// # extern crate serde;
+    /// # extern crate serde_xml_rs;
+    /// # use serde::Deserialize;
+    /// # use serde_xml_rs::Deserializer;
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct E {
+    ///     #[serde(rename = "@attr")]
+    ///     from_attribute: String,
+    ///     #[serde(rename = "attr")]
+    ///     from_element: String,
+    /// }
+    /// # fn main() {
+    /// let s = r#"<e attr="x"><attr>y</attr></e>"#;
+    /// let mut de = Deserializer::new_from_reader(s.as_bytes()).attribute_prefix("@");
+    /// let e = E::deserialize(&mut de).unwrap();
+    /// assert_eq!(e, E { from_attribute: "x".to_string(), from_element: "y".to_string() });
+    /// # }
+    /// ```
+    pub fn attribute_prefix(mut self, prefix: &str) -> Self {
+        self.attribute_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Fail with an error if the root element isn't called one of `roots`, before
+    /// anything inside it is deserialized.
+    pub fn allowed_roots(mut self, roots: &[&str]) -> Self {
+        self.allowed_roots
+            .extend(roots.iter().map(|root| root.to_string()));
+        self
+    }
+
+    /// Collect processing instructions instead of skipping them, and hand them to the
+    /// innermost enclosing struct with a `$pi` field as a sequence of `(target, data)`
+    /// pairs.
+    pub fn capture_processing_instructions(mut self, enabled: bool) -> Self {
+        self.capture_processing_instructions = enabled;
+        self
+    }
+
+    /// Keep track of whether text comes from a CDATA section or not, so that fields
+    /// using `serde_xml_rs::cdata` only accept CDATA. Text and CDATA sections next to
+    /// each other are then no longer merged into one text node by the parser.
+    ///
+    /// This has to be set before anything is read. A deserializer created with `new`
+    /// doesn't control its reader's configuration, so that reader must have been
+    /// created with `cdata_to_characters(false)` for this to take effect.
+    pub fn distinguish_cdata(mut self, enabled: bool) -> Self {
+        self.distinguish_cdata = enabled;
+        self.reconfigure(|config| config.cdata_to_characters(!enabled))
+    }
+
+    /// Trim the whitespace around text content, which is the default. Disable it to
+    /// keep text such as preformatted content exactly as it is, e.g. to read
+    /// `<pre>  hi  </pre>` as `"  hi  "`. Text that is only whitespace, like the
+    /// indentation between elements, is skipped either way.
+    ///
+    /// This has to be set before anything is read, and has no effect on a deserializer
+    /// created with `new`, whose reader is configured by the caller.
+    pub fn trim_whitespace(self, enabled: bool) -> Self {
+        self.reconfigure(|config| config.trim_whitespace(enabled))
+    }
+
+    /// Recreate the reader with its configuration changed by `change`, if that
+    /// configuration is known.
+    fn reconfigure<F: FnOnce(ParserConfig) -> ParserConfig>(mut self, change: F) -> Self {
+        if let Some(config) = self.config.take() {
+            let config = change(config);
+            self.reader = EventReader::new_with_config(self.reader.into_inner(), reader_config(&config));
+            self.trim_text = config.trim_whitespace;
+            self.config = Some(config);
+        }
+        self
+    }
+
+    /// Treat a completely empty child element (`<e/>` or `<e></e>`, without attributes)
+    /// as if it were absent, so the field it would fill falls back on its serde
+    /// default instead.
+    pub fn empty_as_default(mut self, enabled: bool) -> Self {
+        self.empty_as_default = enabled;
+        self
+    }
+
+    /// Match child elements to fields on their name without any trailing digits, so
+    /// numbered siblings like `<addr1>` and `<addr2>` fill a single `addr` sequence.
+    pub fn strip_trailing_digits(mut self, enabled: bool) -> Self {
+        self.element_keys.strip_trailing_digits = enabled;
+        self
+    }
+
+    /// Match child elements to fields on their name without any leading or trailing
+    /// underscores, so `<_internal>` fills an `internal` field.
+    pub fn strip_underscores(mut self, enabled: bool) -> Self {
+        self.element_keys.strip_underscores = enabled;
+        self
+    }
+
+    /// Deserialize an attribute into a sequence field as a one-element sequence holding
+    /// its whole value, so `tags="a"` can fill a `Vec<String>`.
+    pub fn singular_attribute_seqs(mut self, enabled: bool) -> Self {
+        self.singular_attribute_seqs = enabled;
+        self
+    }
+
+    /// Fail with an error once the text content read by this deserializer adds up to
+    /// more than `max` bytes, to bound the memory a document can make it allocate.
+    pub fn set_max_text_bytes(&mut self, max: usize) {
+        self.max_text_bytes = Some(max);
+    }
+
+    /// Fail with an error on any element with more than `max` attributes.
+    pub fn set_max_attributes(&mut self, max: usize) {
+        self.max_attributes = Some(max);
+    }
+
+    /// Treat elements and attributes called `alias` as if they were called `name`, e.g.
+    /// to accept documents from sources that disagree on a field's name.
+    pub fn add_alias(&mut self, alias: &str, name: &str) {
+        self.aliases.insert(alias.to_string(), name.to_string());
+    }
+
+    /// Keep a copy of the attributes of each element as it is entered, available from
+    /// `current_attributes`.
+    pub fn expose_attributes(mut self, enabled: bool) -> Self {
+        self.expose_attributes = enabled;
+        self
+    }
+
+    /// The complete attributes of the element entered most recently, regardless of
+    /// which of them were matched to fields. Always empty unless `expose_attributes`
+    /// is enabled.
+    ///
+    /// These can only be read between calls that deserialize from this `Deserializer`,
+    /// such as `deserialize_element`, as it is borrowed for as long as a value is being
+    /// deserialized. Use `on_start_element` to see the attributes of every element
+    /// while a value is being deserialized.
+    pub fn current_attributes(&self) -> &[OwnedAttribute] {
+        &self.current_attributes
+    }
+
+    /// Where the key yielded most recently to a struct or map visitor came from, or
+    /// `None` if no key has been yielded yet.
+    pub fn last_key_source(&self) -> Option<KeySource> {
+        self.last_key_source
+    }
+
+    /// Fail on child elements that don't match a field of the struct they are in, like
+    /// `#[serde(deny_unknown_fields)]` does, but for every struct. Unknown attributes
+    /// are still ignored.
+    pub fn strict(mut self, enabled: bool) -> Self {
+        self.strict = enabled;
+        self
+    }
+
+    /// Match element and attribute names to struct fields regardless of ASCII case, so
+    /// that `<ID>` or `<Id>` fills a field called `id`. A field whose name matches
+    /// exactly is still preferred.
+    pub fn ignore_case(mut self, enabled: bool) -> Self {
+        self.ignore_case = enabled;
+        self
+    }
+
+    /// Fail with `ErrorKind::UnexpectedRoot` if a struct is read from a root element not
+    /// named after it, i.e. after the struct's name or its `#[serde(rename)]`. Any
+    /// root element name is accepted by default.
+    pub fn check_root_name(mut self, enabled: bool) -> Self {
+        self.check_root_name = enabled;
+        self
+    }
+
+    /// Read the input as a fragment, which can have any number of top-level elements.
+    /// A struct or map is then read from the top-level elements themselves, rather than
+    /// from a single root element around them. Sequences are read from repeated
+    /// top-level elements with or without this. Text outside of elements is still an
+    /// error.
+    pub fn fragment(mut self, enabled: bool) -> Self {
+        self.fragment = enabled;
+        self
+    }
+
+    /// Keep track of which fields of each struct were found in the document, rather
+    /// than left to their defaults, available from `last_populated_fields`.
+    pub fn record_populated_fields(mut self, enabled: bool) -> Self {
+        self.record_populated_fields = enabled;
+        self
+    }
+
+    /// The fields of the struct deserialized most recently that were found in the
+    /// document, in document order. For nested structs that is the outermost one, as
+    /// it is finished last. Always empty unless `record_populated_fields` is enabled.
+    pub fn last_populated_fields(&self) -> &[String] {
+        &self.last_populated_fields
+    }
+
+    /// The position of the event read most recently, e.g. to report where
+    /// deserialization failed. That is where the event starts, except for text, which
+    /// is placed where it ends.
+    pub fn position(&self) -> TextPosition {
+        self.position
+    }
+
+    /// Record the current position in `error`, available from `Error::position`,
+    /// unless it already has one. Errors returned from deserializing with a
+    /// `Deserializer` already have their position recorded.
+    pub fn locate(&self, error: Error) -> Error {
+        error::locate(error, self.position)
+    }
+
+    /// The `xml:base` in scope at the current position, resolved against the bases of
+    /// the enclosing elements.
+    pub fn current_base(&self) -> Option<&str> {
+        self.bases.last().map(|(_, base)| base.as_str())
+    }
+
+    /// Skips ahead to the next element called `name` and deserializes it as `T`,
+    /// ignoring everything that comes before it.
+    pub fn deserialize_element<'de, T: de::Deserialize<'de>>(&mut self, name: &str) -> Result<T> {
+        loop {
+            match *self.peek()? {
+                XmlEvent::StartElement { name: ref start_name, .. }
+                    if start_name.local_name == name => return T::deserialize(&mut *self),
+                XmlEvent::EndDocument => {
+                    return Err(ErrorKind::Custom(format!("Element <{}> not found", name)).into())
+                },
+                _ => {},
+            }
+            self.next()?;
+        }
+    }
+
+    /// Deserializes every child element called `name` of the element currently being
+    /// read (of the root element, if none has been entered yet) as `T`, skipping the
+    /// children with other names. Stops before the end tag of that element.
+    pub fn deserialize_all<'de, T: de::Deserialize<'de>>(&mut self, name: &str) -> Result<Vec<T>> {
+        if self.depth == 0 {
+            loop {
+                match *self.peek()? {
+                    XmlEvent::StartElement { .. } => break,
+                    XmlEvent::EndDocument => return Ok(Vec::new()),
+                    _ => {},
+                }
+                self.next()?;
+            }
+            self.next()?;
+        }
+
+        let mut items = Vec::new();
+        loop {
+            let matches = match *self.peek()? {
+                XmlEvent::StartElement { name: ref start_name, .. } => {
+                    start_name.local_name == name
+                },
+                XmlEvent::EndElement { .. } | XmlEvent::EndDocument => return Ok(items),
+                _ => {
+                    self.next()?;
+                    continue;
+                },
+            };
+            if matches {
+                items.push(T::deserialize(&mut *self)?);
+            } else {
+                de::Deserializer::deserialize_ignored_any(&mut *self, de::IgnoredAny)?;
+            }
+        }
+    }
+
+    fn default_config() -> ParserConfig {
+        ParserConfig::new()
             .trim_whitespace(true)
             .whitespace_to_characters(true)
             .cdata_to_characters(true)
             .ignore_comments(true)
-            .coalesce_characters(true);
-
-        Self::new(EventReader::new_with_config(reader, config))
+            .coalesce_characters(true)
     }
 
     fn peek(&mut self) -> Result<&XmlEvent> {
         if self.peeked.is_none() {
-            self.peeked = Some(self.inner_next()?);
+            self.peeked = Some(self.fetch()?);
         }
         debug_expect!(self.peeked.as_ref(), Some(peeked) => {
             debug!("Peeked {:?}", peeked);
@@ -98,30 +693,166 @@ impl<'de, R: Read> Deserializer<R> {
         })
     }
 
+    /// Whether the next event starts an element without attributes that ends straight
+    /// away.
+    fn next_is_empty_element(&mut self) -> Result<bool> {
+        match *self.peek()? {
+            XmlEvent::StartElement { ref attributes, .. } if attributes.is_empty() => {},
+            _ => return Ok(false),
+        }
+        if self.lookahead.is_none() {
+            self.lookahead = Some(self.inner_next()?);
+        }
+        Ok(matches!(self.lookahead, Some(XmlEvent::EndElement { .. })))
+    }
+
+    fn fetch(&mut self) -> Result<XmlEvent> {
+        match self.lookahead.take() {
+            Some(event) => Ok(event),
+            None => self.inner_next(),
+        }
+    }
+
     fn inner_next(&mut self) -> Result<XmlEvent> {
         loop {
-            match self.reader.next().map_err(ErrorKind::Syntax)? {
+            let event = self.reader.next().map_err(reader_error)?;
+            let position = self.reader.position();
+            match event {
+                XmlEvent::ProcessingInstruction { name, data } => {
+                    if self.capture_processing_instructions {
+                        self.processing_instructions
+                            .push((name, data.unwrap_or_default()));
+                    }
+                },
                 XmlEvent::StartDocument { .. } |
-                XmlEvent::ProcessingInstruction { .. } |
-                XmlEvent::Comment(_) => { /* skip */ },
-                other => return Ok(other),
+                XmlEvent::Comment(_) |
+                XmlEvent::Whitespace(_) => { /* skip */ },
+                XmlEvent::Characters(ref text) if self.trim_text && text.trim().is_empty() => {},
+                other => {
+                    let other = match other {
+                        XmlEvent::Characters(text) if self.trim_text => {
+                            let trimmed = text.trim();
+                            self.trimmed_edges.push_back((
+                                trimmed.len() < text.trim_end().len(),
+                                trimmed.len() < text.trim_start().len(),
+                            ));
+                            XmlEvent::Characters(trimmed.to_string())
+                        },
+                        other => other,
+                    };
+                    if self.distinguish_cdata {
+                        match other {
+                            XmlEvent::CData(_) => self.cdata_flags.push_back(true),
+                            XmlEvent::Characters(_) => self.cdata_flags.push_back(false),
+                            _ => {},
+                        }
+                    }
+                    self.event_positions.push_back(position);
+                    return Ok(self.normalize(other));
+                },
             }
         }
     }
 
+    fn normalize(&self, event: XmlEvent) -> XmlEvent {
+        let event = if self.ignore_namespaces {
+            strip_namespaces(event)
+        } else {
+            event
+        };
+        let event = match self.name_map {
+            Some(ref name_map) => rename(event, &**name_map),
+            None => event,
+        };
+        let event = if self.aliases.is_empty() {
+            event
+        } else {
+            rename(event, &|name: &str| match self.aliases.get(name) {
+                Some(aliased) => Cow::Owned(aliased.clone()),
+                None => Cow::Borrowed(name),
+            })
+        };
+        let event = match event {
+            XmlEvent::CData(text) => XmlEvent::Characters(text),
+            other => other,
+        };
+        match event {
+            XmlEvent::Characters(ref text) if self.strip_text_bom && text.starts_with('\u{feff}') => {
+                XmlEvent::Characters(text['\u{feff}'.len_utf8()..].to_string())
+            },
+            other => other,
+        }
+    }
+
     fn next(&mut self) -> Result<XmlEvent> {
         let next = if let Some(peeked) = self.peeked.take() {
             peeked
         } else {
-            self.inner_next()?
+            self.fetch()?
         };
+        if let Some(position) = self.event_positions.pop_front() {
+            self.position = position;
+        }
         match next {
-            XmlEvent::StartElement { .. } => {
+            XmlEvent::StartElement { ref name, ref attributes, .. } => {
+                if let Some(max_attributes) = self.max_attributes {
+                    if attributes.len() > max_attributes {
+                        return Err(ErrorKind::Custom(format!(
+                            "Element <{}> has {} attributes, more than the limit of {}",
+                            name.local_name,
+                            attributes.len(),
+                            max_attributes
+                        )).into());
+                    }
+                }
+                if self.depth == 0 && !self.allowed_roots.is_empty() &&
+                    !self.allowed_roots.contains(&name.local_name)
+                {
+                    return Err(ErrorKind::Custom(format!(
+                        "Root element <{}> is not one of the allowed roots: {}",
+                        name.local_name,
+                        self.allowed_roots.join(", ")
+                    )).into());
+                }
                 self.depth += 1;
+                if let Some(ref mut hook) = self.start_element_hook {
+                    hook(name, attributes);
+                }
+                if self.expose_attributes {
+                    self.current_attributes = attributes.clone();
+                }
+                let base = attributes.iter().find(|attr| is_xml_base(&attr.name));
+                if let Some(base) = base {
+                    let resolved = match self.current_base() {
+                        Some(parent) => resolve_reference(parent, &base.value),
+                        None => base.value.clone(),
+                    };
+                    self.bases.push((self.depth, resolved));
+                }
             },
             XmlEvent::EndElement { .. } => {
+                if self.bases.last().is_some_and(|&(depth, _)| depth == self.depth) {
+                    self.bases.pop();
+                }
                 self.depth -= 1;
             },
+            XmlEvent::Characters(ref text) => {
+                if self.distinguish_cdata {
+                    self.text_is_cdata = self.cdata_flags.pop_front().unwrap_or(false);
+                }
+                if self.trim_text {
+                    self.text_edges = self.trimmed_edges.pop_front().unwrap_or((false, false));
+                }
+                self.text_bytes += text.len();
+                if let Some(max_text_bytes) = self.max_text_bytes {
+                    if self.text_bytes > max_text_bytes {
+                        return Err(ErrorKind::Custom(format!(
+                            "Text content exceeds the limit of {} bytes",
+                            max_text_bytes
+                        )).into());
+                    }
+                }
+            },
             _ => {},
         }
         debug!("Fetched {:?}", next);
@@ -132,11 +863,16 @@ impl<'de, R: Read> Deserializer<R> {
         self.is_map_value = true;
     }
 
+    fn set_mixed_content(&mut self, enabled: bool) {
+        self.mixed_content = enabled;
+    }
+
     pub fn unset_map_value(&mut self) -> bool {
+        self.newtype_variant = false;
         ::std::mem::replace(&mut self.is_map_value, false)
     }
 
-    fn read_inner_value<V: de::Visitor<'de>, T, F: FnOnce(&mut Self) -> Result<T>>(
+    fn read_inner_value<T, F: FnOnce(&mut Self) -> Result<T>>(
         &mut self,
         f: F,
     ) -> Result<T> {
@@ -151,6 +887,18 @@ impl<'de, R: Read> Deserializer<R> {
         }
     }
 
+    /// Reads the start of the element a struct or map is read from, or nothing at the
+    /// top level of a fragment, which is read as the content of an element without
+    /// attributes.
+    fn next_start_element(&mut self) -> Result<(Option<OwnedName>, Vec<OwnedAttribute>)> {
+        if ::std::mem::replace(&mut self.fragment, false) && self.depth == 0 {
+            return Ok((None, Vec::new()));
+        }
+        expect!(self.next()?, XmlEvent::StartElement { name, attributes, .. } => {
+            Ok((Some(name), attributes))
+        })
+    }
+
     fn expect_end_element(&mut self, start_name: OwnedName) -> Result<()> {
         expect!(self.next()?, XmlEvent::EndElement { name, .. } => {
             if name == start_name {
@@ -165,105 +913,472 @@ impl<'de, R: Read> Deserializer<R> {
         })
     }
 
-    fn prepare_parse_type<V: de::Visitor<'de>>(&mut self) -> Result<String> {
+    /// Reads the text of the current element and its descendants, joining the text
+    /// nodes with `separator`.
+    fn read_inner_text(&mut self, separator: &str) -> Result<String> {
         if let XmlEvent::StartElement { .. } = *self.peek()? {
             self.set_map_value()
         }
-        self.read_inner_value::<V, String, _>(|this| {
+        self.read_inner_value::<String, _>(|this| this.read_remaining_text(separator))
+    }
+
+    /// Reads the rest of the current element's content, keeping only the text of it
+    /// and its descendants, joined with `separator`.
+    fn read_remaining_text(&mut self, separator: &str) -> Result<String> {
+        let depth = self.depth;
+        let mut texts = Vec::new();
+        loop {
+            let at_own_level = self.depth == depth;
+            match *self.peek()? {
+                XmlEvent::EndElement { .. } if at_own_level => break,
+                XmlEvent::EndDocument => break,
+                _ => {},
+            }
+            if let XmlEvent::Characters(text) = self.next()? {
+                texts.push(text);
+            }
+        }
+        Ok(texts.join(separator))
+    }
+
+    /// Writes the content of the current element back out as markup, declaring the
+    /// namespaces it uses where they are first needed.
+    fn read_raw_xml(&mut self) -> Result<String> {
+        if let XmlEvent::StartElement { .. } = *self.peek()? {
+            self.set_map_value()
+        }
+        self.read_inner_value::<String, _>(|this| {
+            let depth = this.depth;
+            let mut xml = String::new();
+            // The namespaces declared by each element open in `xml`, by prefix.
+            let mut scopes: Vec<Vec<(Option<String>, String)>> = Vec::new();
+            loop {
+                let at_own_level = this.depth == depth;
+                match *this.peek()? {
+                    XmlEvent::EndElement { .. } if at_own_level => break,
+                    XmlEvent::EndDocument => break,
+                    _ => {},
+                }
+                match this.next()? {
+                    XmlEvent::StartElement { name, attributes, .. } => {
+                        let _ = write!(xml, "<{}", name.borrow().repr_display());
+                        let mut declared = Vec::new();
+                        let names = Some(&name)
+                            .into_iter()
+                            .chain(attributes.iter().map(|attr| &attr.name));
+                        for used in names {
+                            let (prefix, namespace) = match (&used.prefix, &used.namespace) {
+                                (Some(prefix), _) if prefix == "xml" => continue,
+                                (prefix, Some(namespace)) => (prefix, namespace),
+                                _ => continue,
+                            };
+                            let in_scope = declared
+                                .iter()
+                                .chain(scopes.iter().rev().flatten())
+                                .find(|&(scoped, _)| scoped == prefix)
+                                .map(|(_, scoped)| scoped);
+                            if in_scope == Some(namespace) {
+                                continue;
+                            }
+                            match *prefix {
+                                Some(ref prefix) => {
+                                    let _ = write!(xml, " xmlns:{}=\"{}\"", prefix, escape_str_attribute(namespace));
+                                },
+                                None => {
+                                    let _ = write!(xml, " xmlns=\"{}\"", escape_str_attribute(namespace));
+                                },
+                            }
+                            declared.push((prefix.clone(), namespace.clone()));
+                        }
+                        for attr in attributes {
+                            let _ = write!(
+                                xml,
+                                " {}=\"{}\"",
+                                attr.name.borrow().repr_display(),
+                                escape_str_attribute(&attr.value)
+                            );
+                        }
+                        if let XmlEvent::EndElement { .. } = *this.peek()? {
+                            this.next()?;
+                            xml.push_str("/>");
+                        } else {
+                            xml.push('>');
+                            scopes.push(declared);
+                        }
+                    },
+                    XmlEvent::EndElement { name } => {
+                        scopes.pop();
+                        let _ = write!(xml, "</{}>", name.borrow().repr_display());
+                    },
+                    XmlEvent::Characters(text) => xml.push_str(&escape_str_pcdata(&text)),
+                    _ => {},
+                }
+            }
+            Ok(xml)
+        })
+    }
+
+    /// Reads a text node, along with any text nodes directly following it. Those are
+    /// left over when something in between was skipped, like a processing instruction,
+    /// and are joined with a space where whitespace was trimmed between them.
+    fn next_text(&mut self) -> Result<String> {
+        let mut text = expect!(self.next()?, XmlEvent::Characters(s) => Ok(s))?;
+        let mut all_cdata = self.text_is_cdata;
+        let mut trimmed_end = self.text_edges.1;
+        while let XmlEvent::Characters(_) = *self.peek()? {
+            debug_expect!(self.next(), Ok(XmlEvent::Characters(more)) => {
+                if trimmed_end || self.text_edges.0 {
+                    text.push(' ');
+                }
+                text.push_str(&more);
+            });
+            trimmed_end = self.text_edges.1;
+            all_cdata &= self.text_is_cdata;
+        }
+        self.text_is_cdata = all_cdata;
+        Ok(text)
+    }
+
+    fn prepare_parse_type(&mut self) -> Result<String> {
+        if let XmlEvent::StartElement { .. } = *self.peek()? {
+            self.set_map_value()
+        }
+        self.read_inner_value::<String, _>(|this| {
             if let XmlEvent::EndElement { .. } = *this.peek()? {
                 return Err(
                     ErrorKind::UnexpectedToken("EndElement".into(), "Characters".into()).into(),
                 );
             }
 
-            expect!(this.next()?, XmlEvent::Characters(s) => {
-                return Ok(s)
-            })
+            this.next_text()
         })
     }
 }
 
+/// The configuration `reader` is created with for `config`. Text is trimmed by the
+/// deserializer instead of the reader, so whitespace-only text is left to arrive as
+/// `Whitespace` and be skipped.
+fn reader_config(config: &ParserConfig) -> ParserConfig {
+    config.clone().trim_whitespace(false).whitespace_to_characters(false)
+}
+
+/// Whether an element has `xsi:nil="true"`.
+fn is_nil(attributes: &[OwnedAttribute]) -> bool {
+    attributes.iter().any(|attr| {
+        attr.name.local_name == "nil" &&
+            (attr.name.namespace.as_deref() == Some(XSI_NAMESPACE) ||
+                attr.name.prefix.as_deref() == Some("xsi")) &&
+            (attr.value == "true" || attr.value == "1")
+    })
+}
+
+fn is_xml_base(name: &OwnedName) -> bool {
+    name.local_name == "base" && name.prefix.as_deref() == Some("xml")
+}
+
+/// Resolves `reference` against `base`, covering absolute references, references
+/// relative to the root of the base's authority and references relative to the base's
+/// last path segment.
+fn resolve_reference(base: &str, reference: &str) -> String {
+    if reference.contains("://") {
+        return reference.to_string();
+    }
+    let authority_end = match base.find("://") {
+        Some(scheme_end) => base[scheme_end + 3..]
+            .find('/')
+            .map_or(base.len(), |path_start| scheme_end + 3 + path_start),
+        None => 0,
+    };
+    if reference.starts_with('/') {
+        return format!("{}{}", &base[..authority_end], reference);
+    }
+    match base[authority_end..].rfind('/') {
+        Some(last_slash) => format!("{}{}", &base[..authority_end + last_slash + 1], reference),
+        None if authority_end > 0 => format!("{}/{}", base, reference),
+        None => reference.to_string(),
+    }
+}
+
+fn strip_namespaces(event: XmlEvent) -> XmlEvent {
+    match event {
+        XmlEvent::StartElement { name, attributes, .. } => XmlEvent::StartElement {
+            name: OwnedName::local(name.local_name),
+            attributes: attributes
+                .into_iter()
+                .map(|attr| OwnedAttribute::new(OwnedName::local(attr.name.local_name), attr.value))
+                .collect(),
+            namespace: Namespace::empty(),
+        },
+        XmlEvent::EndElement { name } => XmlEvent::EndElement {
+            name: OwnedName::local(name.local_name),
+        },
+        other => other,
+    }
+}
+
+/// Which tokens are read as booleans, from element text and attribute values alike.
+#[derive(Debug, Clone, Copy, Default)]
+struct BoolTokens {
+    capitalized: bool,
+    xsd: bool,
+}
+
+impl BoolTokens {
+    fn parse(&self, value: &str) -> Result<bool> {
+        if self.xsd {
+            return match value {
+                "true" | "1" => Ok(true),
+                "false" | "0" => Ok(false),
+                _ => Err(ErrorKind::Custom(format!(
+                    "`{}` is not an xsd:boolean, expected true, false, 1 or 0",
+                    value
+                )).into()),
+            };
+        }
+        match value {
+            "true" | "1" | "yes" => Ok(true),
+            "false" | "0" | "no" => Ok(false),
+            "True" if self.capitalized => Ok(true),
+            "False" if self.capitalized => Ok(false),
+            _ => Err(ErrorKind::Custom(format!(
+                "`{}` is not a boolean, expected true, false, 1, 0, yes or no",
+                value
+            )).into()),
+        }
+    }
+}
+
+/// How the names of child elements are turned into the keys matched to fields.
+#[derive(Debug, Clone, Copy, Default)]
+struct ElementKeys {
+    strip_trailing_digits: bool,
+    strip_underscores: bool,
+    qualified_names: bool,
+}
+
+impl ElementKeys {
+    /// The key a child element called `local_name` is matched to fields by.
+    fn key<'n>(&self, local_name: &'n str) -> &'n str {
+        let mut key = local_name;
+        if self.strip_underscores {
+            key = key.trim_matches('_');
+        }
+        if self.strip_trailing_digits {
+            key = key.trim_end_matches(|c: char| c.is_ascii_digit());
+        }
+        key
+    }
+
+    /// The key a child element called `name` is matched to fields by, prefixed with
+    /// `{namespace}` if the element has one and qualified names are enabled.
+    fn name_key<'n>(&self, name: &'n OwnedName) -> Cow<'n, str> {
+        let key = self.key(&name.local_name);
+        match name.namespace {
+            Some(ref namespace) if self.qualified_names => {
+                Cow::Owned(format!("{{{}}}{}", namespace, key))
+            },
+            _ => Cow::Borrowed(key),
+        }
+    }
+
+    /// The key an attribute called `name` is matched to fields by, before any
+    /// attribute prefix is added.
+    fn attribute_key(&self, name: OwnedName) -> String {
+        match name.namespace {
+            Some(ref namespace) if self.qualified_names => {
+                format!("{{{}}}{}", namespace, name.local_name)
+            },
+            _ => name.local_name,
+        }
+    }
+}
+
+fn rename<F: ?Sized + Fn(&str) -> Cow<str>>(mut event: XmlEvent, name_map: &F) -> XmlEvent {
+    let rename_one = |name: &mut OwnedName| {
+        let renamed = match name_map(&name.local_name) {
+            Cow::Borrowed(renamed) if renamed == name.local_name => None,
+            renamed => Some(renamed.into_owned()),
+        };
+        if let Some(renamed) = renamed {
+            name.local_name = renamed;
+        }
+    };
+    match event {
+        XmlEvent::StartElement { ref mut name, ref mut attributes, .. } => {
+            rename_one(name);
+            for attr in attributes {
+                rename_one(&mut attr.name);
+            }
+        },
+        XmlEvent::EndElement { ref mut name } => rename_one(name),
+        _ => {},
+    }
+    event
+}
+
 macro_rules! deserialize_type {
     ($deserialize:ident => $visit:ident) => {
-        fn $deserialize<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-            let value = self.prepare_parse_type::<V>()?.parse()?;
+        fn $deserialize<'de, V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+            let value = self.prepare_parse_type()?.parse()?;
             visitor.$visit(value)
         }
     }
 }
 
-impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
-    type Error = Error;
-
-    forward_to_deserialize_any! {
-        newtype_struct identifier
+// The deserialization itself. These have the names of the `de::Deserializer` methods,
+// which only wrap them to record where errors occurred, and take precedence over them
+// when called on a `Deserializer`.
+impl<R: Read> Deserializer<R> {
+    fn deserialize_newtype_struct<'de, V: de::Visitor<'de>>(
+        &mut self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        if name == inner_text::TOKEN {
+            let text = self.read_inner_text(" ")?;
+            return visitor.visit_string(text);
+        }
+        if name == coalesced_text::TOKEN {
+            let text = self.read_inner_text("")?;
+            return visitor.visit_string(text);
+        }
+        if name == raw::TOKEN {
+            let xml = self.read_raw_xml()?;
+            return visitor.visit_string(xml);
+        }
+        if name == cdata::TOKEN {
+            let text = self.prepare_parse_type()?;
+            if self.distinguish_cdata && !self.text_is_cdata {
+                return Err(ErrorKind::Custom(
+                    "Expected a CDATA section, found plain text".to_string(),
+                ).into());
+            }
+            return visitor.visit_string(text);
+        }
+        self.deserialize_any(visitor)
     }
 
-    fn deserialize_struct<V: de::Visitor<'de>>(
-        self,
-        _name: &'static str,
+    fn deserialize_struct<'de, V: de::Visitor<'de>>(
+        &mut self,
+        struct_name: &'static str,
         fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
+        self.mixed_content = false;
         self.unset_map_value();
-        expect!(self.next()?, XmlEvent::StartElement { name, attributes, .. } => {
-            let map_value = visitor.visit_map(MapAccess::new(
-                self,
-                attributes,
-                fields.contains(&"$value")
-            ))?;
+        let capture_processing_instructions =
+            self.capture_processing_instructions && fields.contains(&"$pi");
+        let record_populated_fields = self.record_populated_fields;
+        let strict = self.strict;
+        let ignore_case = self.ignore_case;
+        let is_root = self.depth == 0;
+        let (name, attributes) = self.next_start_element()?;
+        match name {
+            Some(ref name) if is_root && self.check_root_name && name.local_name != struct_name => {
+                return Err(ErrorKind::UnexpectedRoot(
+                    struct_name.to_string(),
+                    name.local_name.clone(),
+                ).into());
+            },
+            _ => {},
+        }
+        if record_populated_fields {
+            self.populated_fields.push(Vec::new());
+        }
+        let mut map = MapAccess::new(self, attributes, fields.contains(&"$value"));
+        if fields.contains(&"$namespace") {
+            map.capture_namespace(name.as_ref().and_then(|name| name.namespace.clone()));
+        }
+        if capture_processing_instructions {
+            map.capture_processing_instructions();
+        }
+        if record_populated_fields {
+            map.record_populated_fields(fields);
+        }
+        if strict {
+            map.deny_unknown_elements(fields);
+        }
+        if ignore_case {
+            map.ignore_case(fields);
+        }
+        let map_value = visitor.visit_map(map);
+        if record_populated_fields {
+            self.last_populated_fields = self.populated_fields.pop().unwrap_or_default();
+        }
+        let map_value = map_value?;
+        if let Some(name) = name {
             self.expect_end_element(name)?;
-            Ok(map_value)
-        })
+        }
+        Ok(map_value)
     }
 
     deserialize_type!(deserialize_i8 => visit_i8);
     deserialize_type!(deserialize_i16 => visit_i16);
     deserialize_type!(deserialize_i32 => visit_i32);
     deserialize_type!(deserialize_i64 => visit_i64);
+    deserialize_type!(deserialize_i128 => visit_i128);
     deserialize_type!(deserialize_u8 => visit_u8);
     deserialize_type!(deserialize_u16 => visit_u16);
     deserialize_type!(deserialize_u32 => visit_u32);
     deserialize_type!(deserialize_u64 => visit_u64);
+    deserialize_type!(deserialize_u128 => visit_u128);
     deserialize_type!(deserialize_f32 => visit_f32);
     deserialize_type!(deserialize_f64 => visit_f64);
-    deserialize_type!(deserialize_bool => visit_bool);
 
-    fn deserialize_char<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.deserialize_string(visitor)
+    fn deserialize_bool<'de, V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+        let value = self.prepare_parse_type()?;
+        visitor.visit_bool(self.bool_tokens.parse(&value)?)
     }
 
-    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_char<'de, V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
         self.deserialize_string(visitor)
     }
 
-    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    // xml-rs reads from any `Read` and hands out every piece of text as an owned,
+    // already unescaped `String`, so nothing can be borrowed from the input and there
+    // is no `visit_borrowed_str` or `visit_borrowed_bytes`, even for a slice. Passing
+    // that `String` on with `visit_string` or `visit_byte_buf` at least doesn't copy it
+    // again; borrowing types such as `Cow<str>` get it as `Owned`, and `&[u8]` fields
+    // can't be deserialized.
+    fn deserialize_str<'de, V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
         self.deserialize_string(visitor)
     }
 
-    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.deserialize_string(visitor)
+    fn deserialize_bytes<'de, V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<'de, V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+        if let XmlEvent::StartElement { .. } = *self.peek()? {
+            self.set_map_value()
+        }
+        self.read_inner_value::<V::Value, _>(|this| {
+            if let XmlEvent::EndElement { .. } = *this.peek()? {
+                return visitor.visit_bytes(b"");
+            }
+            visitor.visit_byte_buf(this.next_text()?.into_bytes())
+        })
     }
 
-    fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_unit<'de, V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
         if let XmlEvent::StartElement { .. } = *self.peek()? {
             self.set_map_value()
         }
-        self.read_inner_value::<V, V::Value, _>(
+        self.read_inner_value::<V::Value, _>(
             |this| expect!(this.peek()?, &XmlEvent::EndElement { .. } => visitor.visit_unit()),
         )
     }
 
-    fn deserialize_unit_struct<V: de::Visitor<'de>>(
-        self,
+    fn deserialize_unit_struct<'de, V: de::Visitor<'de>>(
+        &mut self,
         _name: &'static str,
         visitor: V,
     ) -> Result<V::Value> {
         self.deserialize_unit(visitor)
     }
 
-    fn deserialize_tuple_struct<V: de::Visitor<'de>>(
-        self,
+    fn deserialize_tuple_struct<'de, V: de::Visitor<'de>>(
+        &mut self,
         _name: &'static str,
         len: usize,
         visitor: V,
@@ -271,54 +1386,89 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
         self.deserialize_tuple(len, visitor)
     }
 
-    fn deserialize_tuple<V: de::Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
-        visitor.visit_seq(SeqAccess::new(self, Some(len)))
+    // A tuple is normally read from repeated elements. An element with attributes, such
+    // as `<kv k="a">b</kv>`, is instead read compactly: attribute values first, then
+    // the element's text.
+    fn deserialize_tuple<'de, V: de::Visitor<'de>>(&mut self, len: usize, visitor: V) -> Result<V::Value> {
+        self.mixed_content = false;
+        self.fragment = false;
+        let compact = self.is_map_value && match *self.peek()? {
+            XmlEvent::StartElement { ref attributes, .. } => !attributes.is_empty(),
+            _ => false,
+        };
+        if compact {
+            self.unset_map_value();
+            debug_expect!(self.next(), Ok(XmlEvent::StartElement { name, attributes, .. }) => {
+                let value = visitor.visit_seq(AttrTupleAccess::new(self, attributes, len))?;
+                self.expect_end_element(name)?;
+                Ok(value)
+            })
+        } else {
+            visitor.visit_seq(SeqAccess::new(self, Some(len)))
+        }
     }
 
-    fn deserialize_enum<V: de::Visitor<'de>>(
-        self,
+    fn deserialize_enum<'de, V: de::Visitor<'de>>(
+        &mut self,
         _name: &'static str,
         _variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
-        self.read_inner_value::<V, V::Value, _>(|this| visitor.visit_enum(EnumAccess::new(this)))
+        self.mixed_content = false;
+        self.read_inner_value::<V::Value, _>(|this| visitor.visit_enum(EnumAccess::new(this)))
     }
 
-    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_string<'de, V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+        // All of the content of an element is the value of its `$value` field, so a
+        // string takes all of its text, including that of child elements.
+        if ::std::mem::replace(&mut self.mixed_content, false) {
+            let text = self.read_remaining_text(" ")?;
+            return visitor.visit_string(text);
+        }
         if let XmlEvent::StartElement { .. } = *self.peek()? {
             self.set_map_value()
         }
-        self.read_inner_value::<V, V::Value, _>(|this| {
+        self.read_inner_value::<V::Value, _>(|this| {
             if let XmlEvent::EndElement { .. } = *this.peek()? {
                 return visitor.visit_str("");
             }
-            expect!(this.next()?, XmlEvent::Characters(s) => {
-                visitor.visit_string(s)
-            })
+            visitor.visit_string(this.next_text()?)
         })
     }
 
-    fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_seq<'de, V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+        self.mixed_content = false;
+        self.fragment = false;
+        if self.newtype_variant {
+            // `<Many><item>1</item><item>2</item></Many>`, whatever the items are named.
+            return self.read_inner_value::<V::Value, _>(|this| visitor.visit_seq(SeqAccess::new(this, None)));
+        }
         visitor.visit_seq(SeqAccess::new(self, None))
     }
 
-    fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_map<'de, V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+        self.mixed_content = false;
         self.unset_map_value();
-        expect!(self.next()?, XmlEvent::StartElement { name, attributes, .. } => {
-            let map_value = visitor.visit_map(MapAccess::new(self, attributes, false))?;
+        let (name, attributes) = self.next_start_element()?;
+        let map_value = visitor.visit_map(MapAccess::new(self, attributes, false))?;
+        if let Some(name) = name {
             self.expect_end_element(name)?;
-            Ok(map_value)
-        })
+        }
+        Ok(map_value)
     }
 
-    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_option<'de, V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
         match *self.peek()? {
             XmlEvent::EndElement { .. } => visitor.visit_none(),
+            XmlEvent::StartElement { ref attributes, .. } if is_nil(attributes) => {
+                self.deserialize_ignored_any(de::IgnoredAny)?;
+                visitor.visit_none()
+            },
             _ => visitor.visit_some(self),
         }
     }
 
-    fn deserialize_ignored_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_ignored_any<'de, V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
         self.unset_map_value();
         let depth = self.depth;
         loop {
@@ -330,7 +1480,11 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
         visitor.visit_unit()
     }
 
-    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+    fn deserialize_identifier<'de, V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_any<'de, V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
         match *self.peek()? {
             XmlEvent::StartElement { .. } => self.deserialize_map(visitor),
             XmlEvent::EndElement { .. } => self.deserialize_unit(visitor),
@@ -338,3 +1492,52 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
         }
     }
 }
+
+macro_rules! locate_errors {
+    ($($deserialize:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $deserialize<V: de::Visitor<'de>>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value> {
+                let result = Deserializer::$deserialize(&mut *self, $($arg,)* visitor);
+                result.map_err(|error| self.locate(error))
+            }
+        )*
+    }
+}
+
+impl<'de, R: Read> de::Deserializer<'de> for &mut Deserializer<R> {
+    type Error = Error;
+
+    locate_errors! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+}
@@ -1,9 +1,11 @@
 use std::io::Read;
 
 use serde::de;
+use xml::attribute::OwnedAttribute;
 use xml::reader::XmlEvent;
 
 use de::Deserializer;
+use de::map::AttrValueDeserializer;
 use error::{Error, Result};
 
 pub struct SeqAccess<'a, R: 'a + Read> {
@@ -14,17 +16,18 @@ pub struct SeqAccess<'a, R: 'a + Read> {
 
 impl<'a, R: 'a + Read> SeqAccess<'a, R> {
     pub fn new(de: &'a mut Deserializer<R>, max_size: Option<usize>) -> Self {
+        let element_keys = de.element_keys;
         let expected_name = if de.unset_map_value() {
-            debug_expect!(de.peek(), Ok(&XmlEvent::StartElement { ref name, .. }) => {
-                Some(name.local_name.clone())
+            debug_expect!(de.peek(), Ok(XmlEvent::StartElement { name, .. }) => {
+                Some(element_keys.name_key(name).into_owned())
             })
         } else {
             None
         };
         SeqAccess {
-            de: de,
-            max_size: max_size,
-            expected_name: expected_name,
+            de,
+            max_size,
+            expected_name,
         }
     }
 }
@@ -45,13 +48,20 @@ impl<'de, 'a, R: 'a + Read> de::SeqAccess<'de> for SeqAccess<'a, R> {
             },
             None => {},
         }
+        let element_keys = self.de.element_keys;
+        let ignore_case = self.de.ignore_case;
         let more = match (self.de.peek()?, self.expected_name.as_ref()) {
-            (&XmlEvent::StartElement { ref name, .. }, Some(expected_name)) => {
-                &name.local_name == expected_name
+            (XmlEvent::StartElement { name, .. }, Some(expected_name)) => {
+                let key = element_keys.name_key(name);
+                if ignore_case {
+                    key.eq_ignore_ascii_case(expected_name)
+                } else {
+                    *key == **expected_name
+                }
             },
-            (&XmlEvent::EndElement { .. }, None) |
+            (XmlEvent::EndElement { .. }, None) |
             (_, Some(_)) |
-            (&XmlEvent::EndDocument { .. }, _) => false,
+            (XmlEvent::EndDocument, _) => false,
             (_, None) => true,
         };
         if more {
@@ -68,3 +78,46 @@ impl<'de, 'a, R: 'a + Read> de::SeqAccess<'de> for SeqAccess<'a, R> {
         self.max_size
     }
 }
+
+/// Reads a tuple out of a single element: first the values of its attributes in
+/// document order, then its text content.
+pub struct AttrTupleAccess<'a, R: 'a + Read> {
+    de: &'a mut Deserializer<R>,
+    attrs: ::std::vec::IntoIter<OwnedAttribute>,
+    remaining: usize,
+}
+
+impl<'a, R: 'a + Read> AttrTupleAccess<'a, R> {
+    pub fn new(de: &'a mut Deserializer<R>, attrs: Vec<OwnedAttribute>, len: usize) -> Self {
+        AttrTupleAccess {
+            de,
+            attrs: attrs.into_iter(),
+            remaining: len,
+        }
+    }
+}
+
+impl<'de, 'a, R: 'a + Read> de::SeqAccess<'de> for AttrTupleAccess<'a, R> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        match self.attrs.next() {
+            Some(OwnedAttribute { value, .. }) => {
+                let value = AttrValueDeserializer::new(value).bool_tokens(self.de.bool_tokens);
+                seed.deserialize(value).map(Some)
+            },
+            None => seed.deserialize(&mut *self.de).map(Some),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
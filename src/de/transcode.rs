@@ -0,0 +1,127 @@
+use std::char;
+use std::io::{self, Cursor, Read};
+
+/// Longest XML declaration that is searched for an `encoding` attribute.
+const MAX_DECLARATION_LEN: usize = 256;
+
+/// A reader that transcodes its input to UTF-8 before it reaches the XML parser.
+///
+/// UTF-16 (either byte order) is detected from a byte order mark, and ISO-8859-1 from
+/// the `encoding` attribute of the XML declaration. Any other input is passed through
+/// untouched.
+pub struct Transcoder<R: Read> {
+    inner: R,
+    buffer: Cursor<Vec<u8>>,
+    // Whether reads fall through to `inner` once `buffer` is drained. This is false
+    // once the whole input had to be decoded into `buffer`.
+    passthrough: bool,
+    sniffed: bool,
+}
+
+impl<R: Read> Transcoder<R> {
+    pub fn new(inner: R) -> Self {
+        Transcoder {
+            inner,
+            buffer: Cursor::new(Vec::new()),
+            passthrough: true,
+            sniffed: false,
+        }
+    }
+
+    fn sniff(&mut self) -> io::Result<()> {
+        let mut head = Vec::new();
+        (&mut self.inner).take(2).read_to_end(&mut head)?;
+
+        match head[..] {
+            [0xFF, 0xFE] => self.decode_utf16(u16::from_le_bytes),
+            [0xFE, 0xFF] => self.decode_utf16(u16::from_be_bytes),
+            [b'<', b'?'] => self.sniff_declaration(head),
+            _ => {
+                self.buffer = Cursor::new(head);
+                Ok(())
+            },
+        }
+    }
+
+    fn decode_utf16(&mut self, to_unit: fn([u8; 2]) -> u16) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        self.inner.read_to_end(&mut bytes)?;
+        if bytes.len() % 2 != 0 {
+            return Err(invalid_data("odd number of bytes in UTF-16 input"));
+        }
+
+        let units = bytes.chunks(2).map(|pair| to_unit([pair[0], pair[1]]));
+        let text = char::decode_utf16(units)
+            .collect::<Result<String, _>>()
+            .map_err(|_| invalid_data("unpaired surrogate in UTF-16 input"))?;
+        self.set_decoded(text.into_bytes());
+        Ok(())
+    }
+
+    fn sniff_declaration(&mut self, mut head: Vec<u8>) -> io::Result<()> {
+        let mut byte = [0];
+        while !head.ends_with(b"?>") && head.len() < MAX_DECLARATION_LEN {
+            if self.inner.read(&mut byte)? == 0 {
+                break;
+            }
+            head.push(byte[0]);
+        }
+
+        if is_latin1(&declared_encoding(&head)) {
+            // The declaration itself is plain ASCII, so only the rest needs decoding.
+            let mut rest = Vec::new();
+            self.inner.read_to_end(&mut rest)?;
+            let text: String = rest.into_iter().map(char::from).collect();
+            head.extend_from_slice(text.as_bytes());
+            self.set_decoded(head);
+        } else {
+            self.buffer = Cursor::new(head);
+        }
+        Ok(())
+    }
+
+    fn set_decoded(&mut self, decoded: Vec<u8>) {
+        self.buffer = Cursor::new(decoded);
+        self.passthrough = false;
+    }
+}
+
+impl<R: Read> Read for Transcoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.sniffed {
+            self.sniffed = true;
+            self.sniff()?;
+        }
+
+        let read = self.buffer.read(buf)?;
+        if read == 0 && self.passthrough && !buf.is_empty() {
+            self.inner.read(buf)
+        } else {
+            Ok(read)
+        }
+    }
+}
+
+fn declared_encoding(declaration: &[u8]) -> String {
+    let declaration = String::from_utf8_lossy(declaration);
+    let value = match declaration.find("encoding") {
+        Some(start) => &declaration[start + "encoding".len()..],
+        None => return String::new(),
+    };
+    value
+        .trim_start_matches(|c: char| c.is_whitespace() || c == '=' || c == '"' || c == '\'')
+        .chars()
+        .take_while(|&c| c != '"' && c != '\'')
+        .collect()
+}
+
+fn is_latin1(encoding: &str) -> bool {
+    matches!(
+        encoding.to_ascii_lowercase().as_str(),
+        "iso-8859-1" | "latin1" | "latin-1" | "l1"
+    )
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
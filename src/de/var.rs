@@ -1,6 +1,6 @@
 use std::io::Read;
 
-use serde::de::{self, Deserializer as SerdeDeserializer, IntoDeserializer};
+use serde::de::{self, IntoDeserializer};
 use xml::name::OwnedName;
 use xml::reader::XmlEvent;
 
@@ -13,7 +13,7 @@ pub struct EnumAccess<'a, R: 'a + Read> {
 
 impl<'a, R: 'a + Read> EnumAccess<'a, R> {
     pub fn new(de: &'a mut Deserializer<R>) -> Self {
-        EnumAccess { de: de }
+        EnumAccess { de }
     }
 }
 
@@ -44,7 +44,7 @@ pub struct VariantAccess<'a, R: 'a + Read> {
 
 impl<'a, R: 'a + Read> VariantAccess<'a, R> {
     pub fn new(de: &'a mut Deserializer<R>) -> Self {
-        VariantAccess { de: de }
+        VariantAccess { de }
     }
 }
 
@@ -67,6 +67,7 @@ impl<'de, 'a, R: 'a + Read> de::VariantAccess<'de> for VariantAccess<'a, R> {
     }
 
     fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        self.de.newtype_variant = true;
         seed.deserialize(&mut *self.de)
     }
 
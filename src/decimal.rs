@@ -0,0 +1,18 @@
+//! Exact decimal text for `rust_decimal::Decimal` values, for use with
+//! `#[serde(with = "serde_xml_rs::decimal")]`.
+//!
+//! The value goes through element text as a string, keeping its scale (so `19.90`
+//! stays `19.90`), and text with more digits than a `Decimal` can hold is an error
+//! rather than being rounded.
+
+use rust_decimal::Decimal;
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S: Serializer>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    Decimal::from_str_exact(text.trim()).map_err(de::Error::custom)
+}
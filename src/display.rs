@@ -0,0 +1,24 @@
+//! Element text for any type with `Display` and `FromStr` implementations, for use
+//! with `#[serde(with = "serde_xml_rs::display")]`.
+//!
+//! Values are written with `Display` and read back with `FromStr`, whatever their
+//! `Serialize` and `Deserialize` implementations (if any) would do.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+pub fn serialize<T: Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    let text = String::deserialize(deserializer)?;
+    text.parse().map_err(de::Error::custom)
+}
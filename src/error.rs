@@ -1,25 +1,42 @@
-use std::fmt::Display;
+use std::error::Error as StdError;
+use std::fmt::{self, Display};
 use serde::de::Error as DeError;
 use serde::ser::Error as SerError;
+use xml::common::{Position, TextPosition};
 
-error_chain! {
-    types {
-        Error,
-        ErrorKind,
-        ResultExt,
-        Result;
-    }
-
-    foreign_links {
-        Io(::std::io::Error);
-        FromUtf8Error(::std::string::FromUtf8Error);
-        ParseIntError(::std::num::ParseIntError);
-        ParseFloatError(::std::num::ParseFloatError);
-        ParseBoolError(::std::str::ParseBoolError);
-        Syntax(::xml::reader::Error);
-    }
-
-    errors {
+quick_error! {
+    /// The kind of an error.
+    #[derive(Debug)]
+    pub enum ErrorKind {
+        /// A convenient variant for String.
+        Msg(s: String) {
+            description(&s)
+            display("{}", s)
+        }
+        Io(err: ::std::io::Error) {
+            description(StdError::description(err))
+            display("{}", err)
+        }
+        FromUtf8Error(err: ::std::string::FromUtf8Error) {
+            description(StdError::description(err))
+            display("{}", err)
+        }
+        ParseIntError(err: ::std::num::ParseIntError) {
+            description(StdError::description(err))
+            display("{}", err)
+        }
+        ParseFloatError(err: ::std::num::ParseFloatError) {
+            description(StdError::description(err))
+            display("{}", err)
+        }
+        ParseBoolError(err: ::std::str::ParseBoolError) {
+            description(StdError::description(err))
+            display("{}", err)
+        }
+        Syntax(err: ::xml::reader::Error) {
+            description(StdError::description(err))
+            display("{}", err)
+        }
         UnexpectedToken(token: String, found: String) {
             description("unexpected token")
             display("Expected token {}, found {}", token, found)
@@ -32,9 +49,32 @@ error_chain! {
             description("unsupported operation")
             display("unsupported operation: '{}'", operation)
         }
+        UnexpectedRoot(expected: String, found: String) {
+            description("unexpected root element")
+            display("Expected root element <{}>, found <{}>", expected, found)
+        }
+        UnexpectedEof(position: TextPosition) {
+            description("unexpected end of input")
+            display("Unexpected end of input at line {}, column {}", position.row + 1, position.column + 1)
+        }
     }
 }
 
+/// The error type of this crate: an `ErrorKind`, and the chain of errors behind it.
+///
+/// This is laid out like an `error_chain` error, but written out so that `Display`
+/// can include the position recorded for the error.
+#[derive(Debug)]
+pub struct Error(
+    /// The kind of the error.
+    pub ErrorKind,
+    /// Contains the error chain and the backtrace.
+    #[doc(hidden)]
+    pub ::error_chain::State,
+);
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
 macro_rules! expect {
     ($actual: expr, $($expected: pat)|+ => $if_ok: expr) => {
         match $actual {
@@ -70,6 +110,137 @@ macro_rules! debug_expect {
     }
 }
 
+impl Error {
+    /// Constructs an error from a kind, and generates a backtrace.
+    pub fn from_kind(kind: ErrorKind) -> Error {
+        Error(kind, ::error_chain::State::default())
+    }
+
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.0
+    }
+
+    /// Iterates over the error chain.
+    pub fn iter(&self) -> ::error_chain::ErrorChainIter<'_> {
+        ::error_chain::ErrorChainIter(Some(self))
+    }
+
+    /// Returns the backtrace associated with this error.
+    pub fn backtrace(&self) -> Option<&::error_chain::Backtrace> {
+        self.1.backtrace()
+    }
+
+    /// The position recorded by `locate`, for errors that don't carry one themselves.
+    fn located(&self) -> Option<&Located> {
+        self.1
+            .next_error
+            .as_ref()
+            .and_then(|error| error.downcast_ref::<Located>())
+    }
+
+    /// Where in the document the error occurred, if it came from parsing one.
+    pub fn position(&self) -> Option<TextPosition> {
+        match *self.kind() {
+            ErrorKind::Syntax(ref error) => return Some(error.position()),
+            ErrorKind::UnexpectedEof(position) => return Some(position),
+            _ => {},
+        }
+        self.located().map(|located| located.position)
+    }
+}
+
+impl StdError for Error {
+    fn description(&self) -> &str {
+        self.0.description()
+    }
+
+    fn cause(&self) -> Option<&dyn StdError> {
+        match self.1.next_error {
+            Some(ref cause) => Some(&**cause),
+            None => match self.0 {
+                ErrorKind::Io(ref err) => err.cause(),
+                ErrorKind::FromUtf8Error(ref err) => err.cause(),
+                ErrorKind::ParseIntError(ref err) => err.cause(),
+                ErrorKind::ParseFloatError(ref err) => err.cause(),
+                ErrorKind::ParseBoolError(ref err) => err.cause(),
+                ErrorKind::Syntax(ref err) => err.cause(),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&self.0, f)?;
+        match self.located() {
+            Some(located) => write!(f, " {}", located),
+            None => Ok(()),
+        }
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Error {
+        Error::from_kind(kind)
+    }
+}
+
+macro_rules! foreign_links {
+    ($($error: ty => $variant: ident,)*) => {
+        $(
+            impl From<$error> for Error {
+                fn from(error: $error) -> Error {
+                    Error::from_kind(ErrorKind::$variant(error))
+                }
+            }
+        )*
+    }
+}
+
+foreign_links! {
+    ::std::io::Error => Io,
+    ::std::string::FromUtf8Error => FromUtf8Error,
+    ::std::num::ParseIntError => ParseIntError,
+    ::std::num::ParseFloatError => ParseFloatError,
+    ::std::str::ParseBoolError => ParseBoolError,
+    ::xml::reader::Error => Syntax,
+}
+
+/// Records `position` in `error`, keeping its kind, unless it already has one.
+pub fn locate(mut error: Error, position: TextPosition) -> Error {
+    if error.position().is_none() {
+        let cause = error.1.next_error.take();
+        error.1.next_error = Some(Box::new(Located { position, cause }));
+    }
+    error
+}
+
+/// The position of an error, kept as the next error in its chain so that the error's
+/// kind stays as it was.
+#[derive(Debug)]
+struct Located {
+    position: TextPosition,
+    cause: Option<Box<dyn StdError + Send>>,
+}
+
+impl Display for Located {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at line {}, column {}", self.position.row + 1, self.position.column + 1)
+    }
+}
+
+impl StdError for Located {
+    fn description(&self) -> &str {
+        "position in the document"
+    }
+
+    fn cause(&self) -> Option<&dyn StdError> {
+        self.cause.as_ref().map(|cause| &**cause as &dyn StdError)
+    }
+}
+
 impl DeError for Error {
     fn custom<T: Display>(msg: T) -> Self {
         ErrorKind::Custom(msg.to_string()).into()
@@ -0,0 +1,23 @@
+//! Reads the text of an element and all of its descendants into a `String`, ignoring
+//! the tags in between, for use with `#[serde(with = "serde_xml_rs::inner_text")]`.
+//!
+//! Text nodes are joined with a single space, as the default parser configuration
+//! trims the whitespace around each of them. `<p>Hello <b>big</b> world</p>` is read
+//! as `"Hello big world"`. Attribute values are not part of the text.
+//!
+//! Serializing writes the string back as plain text content.
+
+use serde::{Deserializer, Serializer};
+
+use token;
+
+/// Newtype struct name that asks `serde_xml_rs::Deserializer` for the inner text.
+pub const TOKEN: &str = "$serde_xml_rs::inner_text";
+
+pub fn serialize<T: AsRef<str>, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(value.as_ref())
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    token::deserialize_token_string(deserializer, TOKEN, "the text content of an element")
+}
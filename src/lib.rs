@@ -33,6 +33,10 @@
 //! ```
 
 
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "rust_decimal")]
+extern crate rust_decimal;
 #[macro_use]
 extern crate error_chain;
 #[macro_use]
@@ -46,11 +50,30 @@ extern crate xml;
 extern crate serde_derive;
 
 #[macro_use]
+#[allow(deprecated)]
 mod error;
+pub mod base64;
+pub mod cdata;
+pub mod coalesced_text;
 pub mod de;
+pub mod display;
+pub mod inner_text;
+pub mod raw;
 pub mod ser;
+mod token;
+#[cfg(feature = "chrono")]
+pub mod datetime;
+#[cfg(feature = "chrono")]
+pub mod xsd_date;
+#[cfg(feature = "rust_decimal")]
+pub mod decimal;
+
+/// Namespace of the `xsi:nil` attribute, which marks an element as a `None` value.
+const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
 
 pub use error::{Error, ErrorKind};
 pub use xml::reader::{EventReader, ParserConfig};
-pub use ser::{to_string, to_writer, Serializer};
-pub use de::{from_reader, from_str, Deserializer};
+pub use ser::{to_string, to_string_forest, to_string_with_root, to_writer, to_writer_forest,
+              to_writer_with_root, LineEnding, Serializer};
+pub use de::{deserialize_if_complete, deserialize_iter, from_reader, from_str, Completion, Deserializer, KeySource};
+pub use raw::RawXml;
@@ -0,0 +1,30 @@
+//! A field holding the markup of an element's content as it is.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use token;
+
+/// Newtype struct name that asks `serde_xml_rs` for raw markup.
+pub const TOKEN: &str = "$serde_xml_rs::raw";
+
+/// The content of an element as XML markup, e.g. `<b>bold</b> text`.
+///
+/// Deserializing writes the element's children back out as markup, with whitespace
+/// trimmed as by the rest of the deserializer. Each namespace the markup uses is
+/// declared on the first element that needs it, so the markup stands on its own.
+/// Serializing writes the markup verbatim as the element's content, so it has to be
+/// well-formed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RawXml(pub String);
+
+impl Serialize for RawXml {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(TOKEN, &self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for RawXml {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RawXml, D::Error> {
+        token::deserialize_token_string(deserializer, TOKEN, "the markup of an element's content").map(RawXml)
+    }
+}
@@ -0,0 +1,151 @@
+use serde::ser::{self, Impossible, Serialize};
+
+use base64;
+use error::{Error, ErrorKind, Result};
+use ser::format_float;
+
+/// Serializes the value of an attribute field to its text, or to `None` if the
+/// attribute should be left out altogether.
+#[derive(Default)]
+pub struct AttrValueSerializer {
+    /// The number of decimals floats are written with, as set by
+    /// `Serializer::fixed_floats`.
+    pub float_decimals: Option<usize>,
+}
+
+macro_rules! serialize_display {
+    ($serialize:ident => $ty:ty) => {
+        fn $serialize(self, v: $ty) -> Result<Self::Ok> {
+            Ok(Some(v.to_string()))
+        }
+    }
+}
+
+fn unsupported(operation: &str) -> Error {
+    ErrorKind::UnsupportedOperation(format!("{} as an attribute value", operation)).into()
+}
+
+#[allow(unused_variables)]
+impl ser::Serializer for AttrValueSerializer {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    type SerializeSeq = Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = Impossible<Self::Ok, Self::Error>;
+    type SerializeStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+    serialize_display!(serialize_bool => bool);
+    serialize_display!(serialize_i8 => i8);
+    serialize_display!(serialize_i16 => i16);
+    serialize_display!(serialize_i32 => i32);
+    serialize_display!(serialize_i64 => i64);
+    serialize_display!(serialize_u8 => u8);
+    serialize_display!(serialize_u16 => u16);
+    serialize_display!(serialize_u32 => u32);
+    serialize_display!(serialize_u64 => u64);
+    serialize_display!(serialize_char => char);
+    serialize_display!(serialize_str => &str);
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
+        Ok(Some(format_float(v, self.float_decimals)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
+        Ok(Some(format_float(v, self.float_decimals)))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok> {
+        Ok(Some(base64::encode(value)))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(Some(String::new()))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok> {
+        Ok(Some(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        Err(unsupported("serialize_newtype_variant"))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(unsupported("serialize_seq"))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        Err(unsupported("serialize_tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(unsupported("serialize_tuple_struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(unsupported("serialize_tuple_variant"))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(unsupported("serialize_map"))
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        Err(unsupported("serialize_struct"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(unsupported("serialize_struct_variant"))
+    }
+}
@@ -2,10 +2,17 @@ use std::io::Write;
 use std::fmt::Display;
 
 use serde::ser::{self, Impossible, Serialize};
+use xml::escape::{escape_str_attribute, escape_str_pcdata};
 
+use base64;
+use cdata;
 use error::{Error, ErrorKind, Result};
-use self::var::{Map, Struct};
+use raw;
+use XSI_NAMESPACE;
+use self::attr::AttrValueSerializer;
+use self::var::{is_valid_name, AttrTuple, Map, Seq, Struct};
 
+mod attr;
 mod var;
 
 
@@ -35,7 +42,7 @@ mod var;
 /// println!("{}", serialized);
 /// # }
 /// ```
-pub fn to_writer<W: Write, S: Serialize>(writer: W, value: &S) -> Result<()> {
+pub fn to_writer<W: Write, S: ?Sized + Serialize>(writer: W, value: &S) -> Result<()> {
     let mut ser = Serializer::new(writer);
     value.serialize(&mut ser)
 }
@@ -64,7 +71,7 @@ pub fn to_writer<W: Write, S: Serialize>(writer: W, value: &S) -> Result<()> {
 /// println!("{}", serialized);
 /// # }
 /// ```
-pub fn to_string<S: Serialize>(value: &S) -> Result<String> {
+pub fn to_string<S: ?Sized + Serialize>(value: &S) -> Result<String> {
     // Create a buffer and serialize our nodes into it
     let mut writer = Vec::with_capacity(128);
     to_writer(&mut writer, value)?;
@@ -74,12 +81,179 @@ pub fn to_string<S: Serialize>(value: &S) -> Result<String> {
     Ok(string)
 }
 
+/// Serializes `value` to a writer as an element called `root`, in place of the name
+/// it would otherwise be written under.
+///
+/// A map is written as the attributes of the root element, so its values have to be
+/// scalars.
+pub fn to_writer_with_root<W: Write, S: ?Sized + Serialize>(
+    writer: W,
+    value: &S,
+    root: &str,
+) -> Result<()> {
+    if !is_valid_name(root) {
+        return Err(ErrorKind::Custom(
+            format!("root `{}` is not a valid XML element name", root),
+        ).into());
+    }
+    let mut ser = Serializer::new(writer);
+    ser.set_pending_tag(root.to_string());
+    ser.root_attributes = true;
+    value.serialize(&mut ser)
+}
+
+/// Serializes `value` to a string as an element called `root`, like
+/// `to_writer_with_root`.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate serde_xml_rs;
+/// # use std::collections::BTreeMap;
+/// # use serde_xml_rs::to_string_with_root;
+/// # fn main() {
+/// let mut settings = BTreeMap::new();
+/// settings.insert("k1", "v1");
+/// settings.insert("k2", "v2");
+///
+/// let serialized = to_string_with_root(&settings, "config").unwrap();
+/// assert_eq!(serialized, r#"<config k1="v1" k2="v2"/>"#);
+/// # }
+/// ```
+pub fn to_string_with_root<S: ?Sized + Serialize>(value: &S, root: &str) -> Result<String> {
+    let mut writer = Vec::with_capacity(128);
+    to_writer_with_root(&mut writer, value, root)?;
+    let string = String::from_utf8(writer)?;
+    Ok(string)
+}
+
+/// Serializes a sequence of `(name, value)` pairs to a writer as a forest of root
+/// elements, each value in an element called `name`.
+pub fn to_writer_forest<W, I, K, V>(writer: W, entries: I) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: Serialize,
+{
+    let mut ser = Serializer::new(writer);
+    for (name, value) in entries {
+        let name = name.as_ref();
+        if !is_valid_name(name) {
+            return Err(ErrorKind::Custom(
+                format!("`{}` is not a valid XML element name", name),
+            ).into());
+        }
+        ser.set_pending_tag(name.to_string());
+        value.serialize(&mut ser)?;
+    }
+    Ok(())
+}
+
+/// Serializes a sequence of `(name, value)` pairs to a string as a forest of root
+/// elements, like `to_writer_forest`.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate serde_xml_rs;
+/// # use serde_xml_rs::to_string_forest;
+/// # fn main() {
+/// let records = vec![("created", 1), ("updated", 2)];
+///
+/// let serialized = to_string_forest(records).unwrap();
+/// assert_eq!(serialized, "<created>1</created><updated>2</updated>");
+/// # }
+/// ```
+pub fn to_string_forest<I, K, V>(entries: I) -> Result<String>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: Serialize,
+{
+    let mut writer = Vec::with_capacity(128);
+    to_writer_forest(&mut writer, entries)?;
+    let string = String::from_utf8(writer)?;
+    Ok(string)
+}
+
+/// The line ending text content is normalized to by `Serializer::line_endings`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
 /// An XML `Serializer`.
+///
+/// Struct fields whose serialized name starts with `@`, e.g. through
+/// `#[serde(rename = "@id")]`, are written as attributes of the struct's element
+/// instead of as child elements. Attributes go into the start tag while it is still
+/// open, so all attribute fields must come before the first child element written for
+/// the struct; an attribute field after a child element is an error. Fields that write
+/// nothing, like `None`, don't close the start tag.
+///
+/// A unit variant is written as an element named after the variant, also inside a
+/// struct field, map entry or sequence element, as in `<color><Red/></color>`. With
+/// `unit_variants_as_text(true)` it is the text of that element instead, as in
+/// `<color>Red</color>`.
+///
+/// A newtype variant holding a sequence, like `Many(vec![1, 2])`, is written as one
+/// `<Many>` element with an `<item>` element per item, as in
+/// `<Many><item>1</item><item>2</item></Many>`, or named after `seq_item_tag` if that
+/// is set. A tuple variant is written as one element per field instead, named after
+/// the variant, as in `<Range>1</Range><Range>2</Range>`.
 pub struct Serializer<W>
 where
     W: Write,
 {
     writer: W,
+    /// Element name the next value should be wrapped in, as set by the enclosing struct
+    /// field or map entry.
+    pending_tag: Option<String>,
+    cdata_threshold: Option<usize>,
+    entry_fallback: bool,
+    line_ending: Option<LineEnding>,
+    /// Whether a map wrapped in the pending tag is written as that element's
+    /// attributes, which only applies to the root given to `to_writer_with_root`.
+    root_attributes: bool,
+    xsi_nil: bool,
+    empty_seq_element: bool,
+    seq_item_tag: Option<String>,
+    self_closing: bool,
+    self_closing_space: bool,
+    unit_variants_as_text: bool,
+    /// Whether the text being written has to be a CDATA section.
+    force_cdata: bool,
+    /// Whether the text being written is markup to be written as it is.
+    raw_text: bool,
+    /// Whether an XML declaration still has to be written before the first element.
+    declaration: bool,
+    declared_version: String,
+    declared_encoding: String,
+    declared_standalone: Option<bool>,
+    wrote_doctype: bool,
+    positional_attributes: bool,
+    float_decimals: Option<usize>,
+    /// Spaces to indent each level of nesting by, if the output is pretty printed.
+    indent: Option<usize>,
+    attribute_per_line: bool,
+    /// Whether the innermost open element's start tag has been written up to, but not
+    /// including, its `>`, which is written before the first child element.
+    unclosed_start_tag: bool,
+    /// Whether the value being written is an element of a sequence without an element
+    /// name, like one at the document root, which can't be written as bare text.
+    untagged_item: bool,
+    /// Whether the pending tag is the name of a newtype variant, so that a sequence
+    /// in it is written as one element rather than one element per item.
+    newtype_variant: bool,
+    /// Number of elements currently open.
+    depth: usize,
+    /// Whether the innermost open element, or the document if none is open, has had a
+    /// child element written into it.
+    has_children: bool,
 }
 
 impl<W> Serializer<W>
@@ -87,22 +261,401 @@ where
     W: Write,
 {
     pub fn new(writer: W) -> Self {
-        Self { writer: writer }
+        Self {
+            writer,
+            pending_tag: None,
+            cdata_threshold: None,
+            entry_fallback: false,
+            line_ending: None,
+            root_attributes: false,
+            xsi_nil: false,
+            empty_seq_element: false,
+            seq_item_tag: None,
+            self_closing: true,
+            self_closing_space: false,
+            unit_variants_as_text: false,
+            force_cdata: false,
+            raw_text: false,
+            declaration: false,
+            declared_version: "1.0".to_string(),
+            declared_encoding: "UTF-8".to_string(),
+            declared_standalone: None,
+            wrote_doctype: false,
+            positional_attributes: false,
+            float_decimals: None,
+            indent: None,
+            attribute_per_line: false,
+            unclosed_start_tag: false,
+            untagged_item: false,
+            newtype_variant: false,
+            depth: 0,
+            has_children: false,
+        }
     }
 
-    fn write_primitive<P: Display>(&mut self, primitive: P) -> Result<()> {
-        write!(self.writer, "{}", primitive)?;
+    /// Write text content as a CDATA section instead of escaping it when it contains
+    /// more than `threshold` of the characters `<`, `>` and `&`, which keeps code-like
+    /// content readable.
+    pub fn auto_cdata(mut self, threshold: usize) -> Self {
+        self.cdata_threshold = Some(threshold);
+        self
+    }
+
+    /// Write map entries whose key isn't a valid element name as
+    /// `<entry key="...">value</entry>` instead of failing with an error.
+    pub fn entry_fallback(mut self, enabled: bool) -> Self {
+        self.entry_fallback = enabled;
+        self
+    }
+
+    /// Convert every line ending (`\r\n`, `\n` or a lone `\r`) in text content to
+    /// `ending`.
+    pub fn line_endings(mut self, ending: LineEnding) -> Self {
+        self.line_ending = Some(ending);
+        self
+    }
+
+    /// Write floats with exactly `decimals` digits after the decimal point, as in
+    /// `3.50`, instead of with as few digits as read back to the same value.
+    pub fn fixed_floats(mut self, decimals: usize) -> Self {
+        self.float_decimals = Some(decimals);
+        self
+    }
+
+    fn attr_value_serializer(&self) -> AttrValueSerializer {
+        AttrValueSerializer {
+            float_decimals: self.float_decimals,
+        }
+    }
+
+    /// Pretty print the output, putting each element on its own line indented by
+    /// `spaces` per level of nesting. Elements containing only text stay on one line,
+    /// as in `<x>5</x>`.
+    pub fn with_indent(mut self, spaces: usize) -> Self {
+        self.indent = Some(spaces);
+        self
+    }
+
+    /// When pretty printing, put each attribute on a line of its own, indented one
+    /// level deeper than its element, instead of all of them on the element's line.
+    pub fn attribute_per_line(mut self, enabled: bool) -> Self {
+        self.attribute_per_line = enabled;
+        self
+    }
+
+    /// Start the document with an XML declaration, `<?xml version="1.0"
+    /// encoding="UTF-8"?>`. Without it, the output can be embedded in other documents.
+    pub fn with_declaration(mut self, enabled: bool) -> Self {
+        self.declaration = enabled;
+        self
+    }
+
+    /// The encoding named in the XML declaration, if `with_declaration` is enabled.
+    /// This only changes the declaration; the output itself is always UTF-8.
+    pub fn declaration_encoding(mut self, encoding: &str) -> Self {
+        self.declared_encoding = encoding.to_string();
+        self
+    }
+
+    /// The XML version named in the XML declaration, `"1.0"` by default, if
+    /// `with_declaration` is enabled.
+    pub fn declaration_version(mut self, version: &str) -> Self {
+        self.declared_version = version.to_string();
+        self
+    }
+
+    /// Add `standalone="yes"` or `standalone="no"` to the XML declaration, if
+    /// `with_declaration` is enabled. By default it is left out.
+    pub fn declaration_standalone(mut self, standalone: Option<bool>) -> Self {
+        self.declared_standalone = standalone;
+        self
+    }
+
+    /// Write tuple structs of scalars as an empty element with their fields as the
+    /// attributes `v0`, `v1`, ..., e.g. `<color v0="255" v1="0" v2="0"/>`. Tuple
+    /// structs can't be serialized otherwise.
+    pub fn positional_attributes(mut self, enabled: bool) -> Self {
+        self.positional_attributes = enabled;
+        self
+    }
+
+    /// Serializes `value` as the next top-level element, after any written before, e.g.
+    /// to write a fragment or a log of several values to one stream. Whatever state a
+    /// previous value left behind, even by failing halfway, is cleared first.
+    pub fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.pending_tag = None;
+        self.root_attributes = false;
+        self.force_cdata = false;
+        self.raw_text = false;
+        self.unclosed_start_tag = false;
+        self.untagged_item = false;
+        self.newtype_variant = false;
+        self.depth = 0;
+        value.serialize(self)
+    }
+
+    /// Write a document type declaration, `<!DOCTYPE doctype>`, after the XML
+    /// declaration if there is one. `doctype` is written as it is, so it can include
+    /// an external ID or an internal subset. This has to be called before the root
+    /// element is serialized, and at most once.
+    pub fn write_doctype(&mut self, doctype: &str) -> Result<()> {
+        if self.depth > 0 || self.has_children {
+            return Err(ErrorKind::Custom(
+                "the DOCTYPE has to be written before the root element".to_string(),
+            ).into());
+        }
+        if ::std::mem::replace(&mut self.wrote_doctype, true) {
+            return Err(ErrorKind::Custom("the DOCTYPE was already written".to_string()).into());
+        }
+        self.write_declaration()?;
+        write!(self.writer, "<!DOCTYPE {}>", doctype)?;
+        if self.indent.is_some() {
+            writeln!(self.writer)?;
+        }
+        Ok(())
+    }
+
+    fn write_declaration(&mut self) -> Result<()> {
+        if ::std::mem::replace(&mut self.declaration, false) {
+            write!(
+                self.writer,
+                "<?xml version=\"{}\" encoding=\"{}\"",
+                escape_str_attribute(&self.declared_version),
+                escape_str_attribute(&self.declared_encoding)
+            )?;
+            match self.declared_standalone {
+                Some(true) => write!(self.writer, " standalone=\"yes\"?>")?,
+                Some(false) => write!(self.writer, " standalone=\"no\"?>")?,
+                None => write!(self.writer, "?>")?,
+            }
+            if self.indent.is_some() {
+                writeln!(self.writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Called before a start tag is written, to keep track of the nesting and to put
+    /// the tag on its own line when pretty printing.
+    fn start_element(&mut self) -> Result<()> {
+        if ::std::mem::replace(&mut self.unclosed_start_tag, false) {
+            write!(self.writer, ">")?;
+        }
+        self.untagged_item = false;
+        if let Some(spaces) = self.indent {
+            if self.depth > 0 || self.has_children {
+                write!(self.writer, "\n{:1$}", "", spaces * self.depth)?;
+            }
+        }
+        self.depth += 1;
+        self.has_children = false;
         Ok(())
     }
 
-    fn write_wrapped<S: Serialize>(&mut self, tag: &str, value: S) -> Result<()> {
-        write!(self.writer, "<{}>", tag)?;
-        value.serialize(&mut *self)?;
-        write!(self.writer, "</{}>", tag)?;
+    /// Called before an end tag, or the `/>` of an empty element, is written. The end
+    /// tag of an element with children goes on its own line when pretty printing.
+    fn end_element(&mut self) -> Result<()> {
+        self.depth -= 1;
+        if let Some(spaces) = self.indent {
+            if self.has_children {
+                write!(self.writer, "\n{:1$}", "", spaces * self.depth)?;
+            }
+        }
+        self.has_children = true;
+        Ok(())
+    }
+
+    /// Writes an attribute into the start tag currently being written.
+    fn write_attribute(&mut self, name: &str, value: &str) -> Result<()> {
+        match self.indent {
+            Some(spaces) if self.attribute_per_line => {
+                write!(self.writer, "\n{:1$}", "", spaces * self.depth)?;
+            },
+            _ => write!(self.writer, " ")?,
+        }
+        write!(self.writer, "{}=\"{}\"", name, escape_str_attribute(value))?;
+        Ok(())
+    }
+
+    fn set_pending_tag(&mut self, tag: String) {
+        self.pending_tag = Some(tag);
+    }
+
+    /// Write `None` as an element marked with `xsi:nil="true"` (declaring the `xsi`
+    /// prefix on the element itself) rather than as an empty element, so that `None`
+    /// and `Some("")` stay apart and sequences keep their positions.
+    pub fn xsi_nil(mut self, enabled: bool) -> Self {
+        self.xsi_nil = enabled;
+        self
+    }
+
+    /// Write an empty sequence in a struct field or map entry as a single empty
+    /// element, `<field/>`, rather than as nothing at all.
+    pub fn empty_seq_element(mut self, enabled: bool) -> Self {
+        self.empty_seq_element = enabled;
+        self
+    }
+
+    /// Write a sequence in a struct field or map entry as a single element named
+    /// after the field, holding one `<item_tag>` child per element, rather than as
+    /// repeated field elements, which is the default. With `Some("item")`, a
+    /// `tags: Vec<String>` field is written as `<tags><item>a</item><item>b</item></tags>`
+    /// instead of `<tags>a</tags><tags>b</tags>`. Reading that shape back takes a
+    /// wrapper struct with an `item` sequence field.
+    pub fn seq_item_tag(mut self, item_tag: Option<&str>) -> Self {
+        self.seq_item_tag = item_tag.map(str::to_string);
+        self
+    }
+
+    /// Write elements without content as `<tag/>`, which is the default, or else as
+    /// `<tag></tag>`. This covers unit structs and variants, structs without fields or
+    /// with only attribute fields, empty strings, and the other elements written
+    /// without content.
+    pub fn self_closing(mut self, enabled: bool) -> Self {
+        self.self_closing = enabled;
+        self
+    }
+
+    /// Write a space before the slash of self-closing tags, as in `<tag />`, rather
+    /// than `<tag/>`, which is the default.
+    pub fn self_closing_space(mut self, enabled: bool) -> Self {
+        self.self_closing_space = enabled;
+        self
+    }
+
+    /// Write a unit variant in a struct field, sequence item or map entry as the text
+    /// of that element, e.g. `<color>Red</color>`, instead of as an element of its own
+    /// inside it, `<color><Red/></color>`, which is the default. Both read back into
+    /// the enum. This also writes the tag of an adjacently tagged enum as text. A unit
+    /// variant at the root is always written as `<Red/>`.
+    pub fn unit_variants_as_text(mut self, enabled: bool) -> Self {
+        self.unit_variants_as_text = enabled;
+        self
+    }
+
+    /// Finishes an element without content, whose start tag has been written up to,
+    /// but not including, its `>`.
+    fn end_empty_element(&mut self, tag: &str) -> Result<()> {
+        self.end_element()?;
+        if self.self_closing && self.self_closing_space {
+            write!(self.writer, " />")?;
+        } else if self.self_closing {
+            write!(self.writer, "/>")?;
+        } else {
+            write!(self.writer, "></{}>", tag)?;
+        }
+        Ok(())
+    }
+
+    fn take_pending_tag(&mut self) -> Option<String> {
+        self.root_attributes = false;
+        self.newtype_variant = false;
+        self.pending_tag.take()
+    }
+
+    fn open_pending_tag(&mut self) -> Result<Option<String>> {
+        self.write_declaration()?;
+        let tag = self.take_pending_tag();
+        match tag {
+            Some(ref tag) => {
+                self.start_element()?;
+                write!(self.writer, "<{}>", tag)?;
+            },
+            None if self.untagged_item => {
+                return Err(ErrorKind::Custom(
+                    "a scalar in a sequence without an element name, like one at the \
+                     document root, has no element to be written in"
+                        .to_string(),
+                ).into());
+            },
+            None => {
+                // Text written straight into the innermost element.
+                if ::std::mem::replace(&mut self.unclosed_start_tag, false) {
+                    write!(self.writer, ">")?;
+                }
+            },
+        }
+        Ok(tag)
+    }
+
+    fn close_tag(&mut self, tag: Option<String>) -> Result<()> {
+        if let Some(tag) = tag {
+            self.end_element()?;
+            write!(self.writer, "</{}>", tag)?;
+        }
         Ok(())
     }
+
+    fn write_primitive<P: Display>(&mut self, primitive: P) -> Result<()> {
+        let tag = self.open_pending_tag()?;
+        write!(self.writer, "{}", primitive)?;
+        self.close_tag(tag)
+    }
+
+    fn write_text(&mut self, text: &str) -> Result<()> {
+        if text.is_empty() && !self.raw_text && !self.force_cdata {
+            return match self.pending_tag {
+                Some(_) => ser::Serializer::serialize_unit(self),
+                None => Ok(()),
+            };
+        }
+        let tag = self.open_pending_tag()?;
+        let normalized;
+        let text = match self.line_ending {
+            Some(ending) => {
+                normalized = normalize_line_endings(text, ending);
+                normalized.as_str()
+            },
+            None => text,
+        };
+        if ::std::mem::replace(&mut self.raw_text, false) {
+            write!(self.writer, "{}", text)?;
+            return self.close_tag(tag);
+        }
+        let markup = text.chars().filter(|&c| c == '<' || c == '>' || c == '&').count();
+        let forced = ::std::mem::replace(&mut self.force_cdata, false);
+        if forced || self.cdata_threshold.is_some_and(|threshold| markup > threshold) {
+            // `]]>` can't appear inside a CDATA section, so end the section between the
+            // `]]` and the `>` and start a new one.
+            write!(
+                self.writer,
+                "<![CDATA[{}]]>",
+                text.replace("]]>", "]]]]><![CDATA[>")
+            )?;
+        } else {
+            write!(self.writer, "{}", escape_str_pcdata(text))?;
+        }
+        self.close_tag(tag)
+    }
+}
+
+/// The text of a float, with the XML Schema spellings `NaN`, `INF` and `-INF` for the
+/// special values.
+///
+/// Without a number of `decimals`, this is the shortest text that parses back to the
+/// same float. That has no decimal point for whole numbers, so `3.0` is written as
+/// `3`, and never uses an exponent.
+fn format_float<F: Into<f64> + Display + Copy>(v: F, decimals: Option<usize>) -> String {
+    let wide = v.into();
+    if wide.is_nan() {
+        "NaN".to_string()
+    } else if wide.is_infinite() {
+        if wide > 0.0 { "INF" } else { "-INF" }.to_string()
+    } else {
+        match decimals {
+            Some(decimals) => format!("{:.*}", decimals, v),
+            None => v.to_string(),
+        }
+    }
 }
 
+fn normalize_line_endings(text: &str, ending: LineEnding) -> String {
+    let lf = text.replace("\r\n", "\n").replace('\r', "\n");
+    match ending {
+        LineEnding::Lf => lf,
+        LineEnding::CrLf => lf.replace('\n', "\r\n"),
+    }
+}
 
 #[allow(unused_variables)]
 impl<'w, W> ser::Serializer for &'w mut Serializer<W>
@@ -112,22 +665,16 @@ where
     type Ok = ();
     type Error = Error;
 
-    type SerializeSeq = Impossible<Self::Ok, Self::Error>;
+    type SerializeSeq = Seq<'w, W>;
     type SerializeTuple = Impossible<Self::Ok, Self::Error>;
-    type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
-    type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = AttrTuple<'w, W>;
+    type SerializeTupleVariant = Seq<'w, W>;
     type SerializeMap = Map<'w, W>;
     type SerializeStruct = Struct<'w, W>;
-    type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+    type SerializeStructVariant = Struct<'w, W>;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
-        if v {
-            write!(self.writer, "true")?;
-        } else {
-            write!(self.writer, "false")?;
-        }
-
-        Ok(())
+        self.write_primitive(v)
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
@@ -163,30 +710,39 @@ where
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
-        self.write_primitive(v)
+        let text = format_float(v, self.float_decimals);
+        self.write_primitive(text)
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
-        self.write_primitive(v)
+        let text = format_float(v, self.float_decimals);
+        self.write_primitive(text)
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok> {
-        self.write_primitive(v)
+        self.write_text(v.encode_utf8(&mut [0; 4]))
     }
 
     fn serialize_str(self, value: &str) -> Result<Self::Ok> {
-        self.write_primitive(value)
+        self.write_text(value)
     }
 
     fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok> {
-        // TODO: I imagine you'd want to use base64 here.
-        // Not sure how to roundtrip effectively though...
-        Err(
-            ErrorKind::UnsupportedOperation("serialize_bytes".to_string()).into(),
-        )
+        self.write_primitive(base64::encode(value))
     }
 
     fn serialize_none(self) -> Result<Self::Ok> {
+        // `None` leaves out the element it would have been written in, unless it's
+        // marked as nil instead.
+        let tag = self.take_pending_tag();
+        if let (Some(tag), true) = (tag, self.xsi_nil) {
+            self.write_declaration()?;
+            self.start_element()?;
+            write!(self.writer, "<{}", tag)?;
+            self.write_attribute("xsi:nil", "true")?;
+            self.write_attribute("xmlns:xsi", XSI_NAMESPACE)?;
+            self.end_empty_element(&tag)?;
+        }
         Ok(())
     }
 
@@ -195,11 +751,20 @@ where
     }
 
     fn serialize_unit(self) -> Result<Self::Ok> {
-        self.serialize_none()
+        self.write_declaration()?;
+        if let Some(tag) = self.take_pending_tag() {
+            self.start_element()?;
+            write!(self.writer, "<{}", tag)?;
+            self.end_empty_element(&tag)?;
+        }
+        Ok(())
     }
 
     fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok> {
-        self.write_wrapped(name, ())
+        if self.pending_tag.is_none() {
+            self.set_pending_tag(name.to_string());
+        }
+        self.serialize_unit()
     }
 
     fn serialize_unit_variant(
@@ -208,9 +773,19 @@ where
         variant_index: u32,
         variant: &'static str,
     ) -> Result<Self::Ok> {
-        Err(
-            ErrorKind::UnsupportedOperation("serialize_unit_variant".to_string()).into(),
-        )
+        // The tag of an adjacently tagged enum arrives here too, in its tag field.
+        if self.unit_variants_as_text && self.pending_tag.is_some() {
+            return self.write_primitive(variant);
+        }
+        self.write_declaration()?;
+        let wrapper = self.take_pending_tag();
+        if let Some(ref wrapper) = wrapper {
+            self.start_element()?;
+            write!(self.writer, "<{}>", wrapper)?;
+        }
+        self.set_pending_tag(variant.to_string());
+        ser::Serializer::serialize_unit(&mut *self)?;
+        self.close_tag(wrapper)
     }
 
     fn serialize_newtype_struct<T: ?Sized + Serialize>(
@@ -218,9 +793,12 @@ where
         name: &'static str,
         value: &T,
     ) -> Result<Self::Ok> {
-        Err(
-            ErrorKind::UnsupportedOperation("serialize_newtype_struct".to_string()).into(),
-        )
+        if name == cdata::TOKEN {
+            self.force_cdata = true;
+        } else if name == raw::TOKEN {
+            self.raw_text = true;
+        }
+        value.serialize(self)
     }
 
     fn serialize_newtype_variant<T: ?Sized + Serialize>(
@@ -230,14 +808,45 @@ where
         variant: &'static str,
         value: &T,
     ) -> Result<Self::Ok> {
-        self.write_wrapped(variant, value)
+        self.write_declaration()?;
+        match self.take_pending_tag() {
+            Some(tag) => {
+                self.start_element()?;
+                write!(self.writer, "<{}>", tag)?;
+                self.set_pending_tag(variant.to_string());
+                self.newtype_variant = true;
+                value.serialize(&mut *self)?;
+                self.end_element()?;
+                write!(self.writer, "</{}>", tag)?;
+                Ok(())
+            },
+            None => {
+                self.set_pending_tag(variant.to_string());
+                self.newtype_variant = true;
+                value.serialize(self)
+            },
+        }
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
-        // TODO: Figure out how to constrain the things written to only be composites
-        Err(
-            ErrorKind::UnsupportedOperation("serialize_seq".to_string()).into(),
-        )
+        // A newtype variant's sequence is the content of the one variant element, with
+        // an `<item>` element per item unless `seq_item_tag` says otherwise.
+        let newtype_variant = self.newtype_variant;
+        let tag = self.take_pending_tag();
+        let item_tag = match self.seq_item_tag {
+            Some(ref item_tag) => Some(item_tag.clone()),
+            None if newtype_variant => Some("item".to_string()),
+            None => None,
+        };
+        match (tag, item_tag) {
+            (Some(wrapper), Some(item_tag)) => {
+                self.write_declaration()?;
+                self.start_element()?;
+                write!(self.writer, "<{}", wrapper)?;
+                Ok(Seq::new(self, Some(item_tag)).wrapped_in(Some(wrapper)))
+            },
+            (tag, _) => Ok(Seq::new(self, tag)),
+        }
     }
 
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
@@ -251,9 +860,16 @@ where
         name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
-        Err(
-            ErrorKind::UnsupportedOperation("serialize_tuple_struct".to_string()).into(),
-        )
+        if !self.positional_attributes {
+            return Err(
+                ErrorKind::UnsupportedOperation("serialize_tuple_struct".to_string()).into(),
+            );
+        }
+        self.write_declaration()?;
+        let tag = self.take_pending_tag().unwrap_or_else(|| name.to_string());
+        self.start_element()?;
+        write!(self.writer, "<{}", tag)?;
+        Ok(AttrTuple::new(self, tag))
     }
 
     fn serialize_tuple_variant(
@@ -263,18 +879,39 @@ where
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        Err(
-            ErrorKind::UnsupportedOperation("serialize_tuple_variant".to_string()).into(),
-        )
+        self.write_declaration()?;
+        let wrapper = self.take_pending_tag();
+        if let Some(ref wrapper) = wrapper {
+            self.start_element()?;
+            write!(self.writer, "<{}", wrapper)?;
+        }
+        Ok(Seq::new(self, Some(variant.to_string())).wrapped_in(wrapper))
     }
 
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
-        Ok(Map::new(self))
+        self.write_declaration()?;
+        let attributes = self.root_attributes;
+        match self.take_pending_tag() {
+            Some(tag) if attributes => {
+                self.start_element()?;
+                write!(self.writer, "<{}", tag)?;
+                Ok(Map::attributes(self, tag))
+            },
+            Some(tag) => {
+                self.start_element()?;
+                write!(self.writer, "<{}>", tag)?;
+                Ok(Map::wrapped(self, tag))
+            },
+            None => Ok(Map::new(self)),
+        }
     }
 
     fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
-        write!(self.writer, "<{}>", name)?;
-        Ok(Struct::new(self, name))
+        self.write_declaration()?;
+        let tag = self.take_pending_tag().unwrap_or_else(|| name.to_string());
+        self.start_element()?;
+        write!(self.writer, "<{}", tag)?;
+        Ok(Struct::open(self, &tag))
     }
 
     fn serialize_struct_variant(
@@ -284,7 +921,17 @@ where
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        Err(ErrorKind::UnsupportedOperation("Result".to_string()).into())
+        // Like a newtype variant: the variant's element goes inside the element for
+        // the enclosing field, if there is one.
+        self.write_declaration()?;
+        let wrapper = self.take_pending_tag();
+        if let Some(ref wrapper) = wrapper {
+            self.start_element()?;
+            write!(self.writer, "<{}>", wrapper)?;
+        }
+        self.start_element()?;
+        write!(self.writer, "<{}", variant)?;
+        Ok(Struct::open(self, variant).wrapped_in(wrapper))
     }
 }
 
@@ -292,6 +939,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::BTreeMap;
     use serde::Serializer as SerSerializer;
     use serde::ser::{SerializeMap, SerializeStruct};
 
@@ -312,6 +960,63 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_serialize_numbers() {
+        assert_eq!(to_string(&-42i8).unwrap(), "-42");
+        assert_eq!(to_string(&u64::MAX).unwrap(), "18446744073709551615");
+
+        let inputs = vec![
+            (0.1f64, "0.1"),
+            (1e21, "1000000000000000000000"),
+            (-2.5, "-2.5"),
+            (f64::NAN, "NaN"),
+            (f64::INFINITY, "INF"),
+            (f64::NEG_INFINITY, "-INF"),
+        ];
+        for (src, should_be) in inputs {
+            assert_eq!(to_string(&src).unwrap(), should_be);
+        }
+
+        assert_eq!(to_string(&0.1f32).unwrap(), "0.1");
+        assert_eq!(to_string(&f32::NEG_INFINITY).unwrap(), "-INF");
+    }
+
+    #[test]
+    fn test_serialize_floats() {
+        #[derive(Serialize)]
+        #[serde(rename = "price")]
+        struct Price {
+            #[serde(rename = "@tax")]
+            tax: f32,
+            amount: f64,
+        }
+
+        let inputs = vec![
+            (3.5f64, "3.5"),
+            (3.0, "3"),
+            (1.0 / 3.0, "0.3333333333333333"),
+            (123456.789012345, "123456.789012345"),
+        ];
+        for (src, should_be) in inputs {
+            assert_eq!(to_string(&src).unwrap(), should_be);
+            assert_eq!(should_be.parse::<f64>().unwrap(), src);
+        }
+
+        let price = Price {
+            tax: 0.5,
+            amount: 3.0,
+        };
+        assert_eq!(to_string(&price).unwrap(), r#"<price tax="0.5"><amount>3</amount></price>"#);
+
+        let mut buffer = Vec::new();
+        {
+            let mut ser = Serializer::new(&mut buffer).fixed_floats(2);
+            price.serialize(&mut ser).unwrap();
+        }
+        let got = String::from_utf8(buffer).unwrap();
+        assert_eq!(got, r#"<price tax="0.50"><amount>3.00</amount></price>"#);
+    }
+
     #[test]
     fn test_start_serialize_struct() {
         let mut buffer = Vec::new();
@@ -322,7 +1027,7 @@ mod tests {
         }
 
         let got = String::from_utf8(buffer).unwrap();
-        assert_eq!(got, "<foo>");
+        assert_eq!(got, "<foo");
     }
 
     #[test]
@@ -364,15 +1069,19 @@ mod tests {
     }
 
     #[test]
-    fn test_serialize_map_entries() {
-        let should_be = "<name>Bob</name><age>5</age>";
+    fn test_serialize_char() {
+        #[derive(Serialize)]
+        struct Separator {
+            symbol: char,
+        }
+
+        let separator = Separator { symbol: '&' };
+        let should_be = "<Separator><symbol>&amp;</symbol></Separator>";
         let mut buffer = Vec::new();
 
         {
             let mut ser = Serializer::new(&mut buffer);
-            let mut map = Map::new(&mut ser);
-            map.serialize_entry("name", "Bob").unwrap();
-            map.serialize_entry("age", "5").unwrap();
+            separator.serialize(&mut ser).unwrap();
         }
 
         let got = String::from_utf8(buffer).unwrap();
@@ -380,22 +1089,28 @@ mod tests {
     }
 
     #[test]
-    fn test_serialize_enum() {
+    fn test_serialize_unit_fields() {
         #[derive(Serialize)]
-        #[allow(dead_code)]
-        enum Node {
-            Boolean(bool),
-            Number(f64),
-            String(String),
+        struct Marker;
+
+        #[derive(Serialize)]
+        struct Flags {
+            unit: (),
+            marker: Marker,
+            count: u32,
         }
 
+        let flags = Flags {
+            unit: (),
+            marker: Marker,
+            count: 1,
+        };
+        let should_be = "<Flags><unit/><marker/><count>1</count></Flags>";
         let mut buffer = Vec::new();
-        let should_be = "<Boolean>true</Boolean>";
 
         {
             let mut ser = Serializer::new(&mut buffer);
-            let node = Node::Boolean(true);
-            node.serialize(&mut ser).unwrap();
+            flags.serialize(&mut ser).unwrap();
         }
 
         let got = String::from_utf8(buffer).unwrap();
@@ -403,19 +1118,976 @@ mod tests {
     }
 
     #[test]
-    #[ignore]
-    fn serialize_a_list() {
-        let inputs = vec![1, 2, 3, 4];
+    fn test_serialize_nested_struct() {
+        #[derive(Serialize)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        #[derive(Serialize)]
+        struct Line {
+            from: Point,
+            to: Point,
+        }
+
+        assert_eq!(to_string(&Point { x: 1, y: 2 }).unwrap(), "<Point><x>1</x><y>2</y></Point>");
+
+        let line = Line {
+            from: Point { x: 1, y: 2 },
+            to: Point { x: 3, y: 4 },
+        };
+        assert_eq!(
+            to_string(&line).unwrap(),
+            "<Line><from><x>1</x><y>2</y></from><to><x>3</x><y>4</y></to></Line>"
+        );
+    }
+
+    #[test]
+    fn test_serialize_attributes_only_struct() {
+        #[derive(Serialize)]
+        #[serde(rename = "point")]
+        struct Point {
+            #[serde(rename = "@x")]
+            x: i32,
+            #[serde(rename = "@y")]
+            y: i32,
+        }
 
         let mut buffer = Vec::new();
 
         {
             let mut ser = Serializer::new(&mut buffer);
-            inputs.serialize(&mut ser).unwrap();
+            Point { x: 1, y: 2 }.serialize(&mut ser).unwrap();
         }
 
         let got = String::from_utf8(buffer).unwrap();
-        println!("{}", got);
-        panic!();
+        assert_eq!(got, r#"<point x="1" y="2"/>"#);
+    }
+
+    #[test]
+    fn test_serialize_attributes_before_children() {
+        #[derive(Serialize)]
+        struct Node {
+            #[serde(rename = "@id")]
+            id: u32,
+            name: String,
+        }
+
+        #[derive(Serialize)]
+        struct Misordered {
+            name: String,
+            #[serde(rename = "@id")]
+            id: u32,
+        }
+
+        let node = Node {
+            id: 1,
+            name: "foo".to_string(),
+        };
+        assert_eq!(to_string(&node).unwrap(), r#"<Node id="1"><name>foo</name></Node>"#);
+
+        let misordered = Misordered {
+            name: "foo".to_string(),
+            id: 1,
+        };
+        assert!(to_string(&misordered).is_err());
+    }
+
+    #[test]
+    fn test_serialize_rename_all() {
+        #[derive(Serialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct Person {
+            first_name: String,
+            last_name: String,
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct Account {
+            #[serde(rename = "@accountId")]
+            account_id: u32,
+            display_name: String,
+        }
+
+        let person = Person {
+            first_name: "Ann".to_string(),
+            last_name: "Lee".to_string(),
+        };
+        assert_eq!(
+            to_string(&person).unwrap(),
+            "<Person><FirstName>Ann</FirstName><LastName>Lee</LastName></Person>"
+        );
+
+        // An explicit rename is used as is, so the attribute keeps its own casing.
+        let account = Account {
+            account_id: 7,
+            display_name: "ann".to_string(),
+        };
+        assert_eq!(
+            to_string(&account).unwrap(),
+            r#"<Account accountId="7"><DisplayName>ann</DisplayName></Account>"#
+        );
+    }
+
+    #[test]
+    fn test_serialize_declaration() {
+        #[derive(Serialize)]
+        struct Person {
+            name: String,
+        }
+
+        let inputs = vec![
+            (None, r#"<Person><name>Bob</name></Person>"#),
+            (
+                Some("UTF-8"),
+                r#"<?xml version="1.0" encoding="UTF-8"?><Person><name>Bob</name></Person>"#,
+            ),
+            (
+                Some("utf-8"),
+                r#"<?xml version="1.0" encoding="utf-8"?><Person><name>Bob</name></Person>"#,
+            ),
+        ];
+
+        for (encoding, should_be) in inputs {
+            let mut buffer = Vec::new();
+
+            {
+                let mut ser = Serializer::new(&mut buffer);
+                if let Some(encoding) = encoding {
+                    ser = ser.with_declaration(true).declaration_encoding(encoding);
+                }
+                let bob = Person {
+                    name: "Bob".to_string(),
+                };
+                bob.serialize(&mut ser).unwrap();
+            }
+
+            let got = String::from_utf8(buffer).unwrap();
+            assert_eq!(got, should_be);
+        }
+
+        let overrides = vec![
+            (
+                Some(true),
+                r#"<?xml version="1.1" encoding="ISO-8859-1" standalone="yes"?><Person><name/></Person>"#,
+            ),
+            (
+                Some(false),
+                r#"<?xml version="1.1" encoding="ISO-8859-1" standalone="no"?><Person><name/></Person>"#,
+            ),
+            (None, r#"<?xml version="1.1" encoding="ISO-8859-1"?><Person><name/></Person>"#),
+        ];
+
+        for (standalone, should_be) in overrides {
+            let mut buffer = Vec::new();
+            {
+                let mut ser = Serializer::new(&mut buffer)
+                    .with_declaration(true)
+                    .declaration_version("1.1")
+                    .declaration_encoding("ISO-8859-1")
+                    .declaration_standalone(standalone);
+                Person { name: String::new() }.serialize(&mut ser).unwrap();
+            }
+            assert_eq!(String::from_utf8(buffer).unwrap(), should_be);
+        }
+
+        let mut buffer = Vec::new();
+        {
+            let mut ser = Serializer::new(&mut buffer).with_declaration(true);
+            "plain".serialize(&mut ser).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>plain"#
+        );
+    }
+
+    #[test]
+    fn test_write_doctype() {
+        #[derive(Serialize)]
+        struct Note {
+            to: String,
+        }
+
+        let note = Note {
+            to: "Tove".to_string(),
+        };
+
+        let mut buffer = Vec::new();
+        {
+            let mut ser = Serializer::new(&mut buffer).with_declaration(true);
+            ser.write_doctype(r#"Note SYSTEM "note.dtd""#).unwrap();
+            note.serialize(&mut ser).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<!DOCTYPE Note SYSTEM "note.dtd">"#,
+                "<Note><to>Tove</to></Note>"
+            )
+        );
+
+        let mut buffer = Vec::new();
+        {
+            let mut ser = Serializer::new(&mut buffer);
+            ser.write_doctype("Note").unwrap();
+            assert!(ser.write_doctype("Note").is_err());
+            note.serialize(&mut ser).unwrap();
+            assert!(ser.write_doctype("Note").is_err());
+        }
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "<!DOCTYPE Note><Note><to>Tove</to></Note>"
+        );
+    }
+
+    #[test]
+    fn test_serialize_values() {
+        #[derive(Serialize)]
+        struct Event {
+            #[serde(rename = "@id")]
+            id: u32,
+            message: String,
+        }
+
+        let events = vec![
+            Event { id: 1, message: "started".to_string() },
+            Event { id: 2, message: "running".to_string() },
+            Event { id: 3, message: "stopped".to_string() },
+        ];
+
+        let mut buffer = Vec::new();
+        {
+            let mut ser = Serializer::new(&mut buffer);
+            for event in &events {
+                ser.serialize_value(event).unwrap();
+            }
+        }
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            concat!(
+                r#"<Event id="1"><message>started</message></Event>"#,
+                r#"<Event id="2"><message>running</message></Event>"#,
+                r#"<Event id="3"><message>stopped</message></Event>"#
+            )
+        );
+
+        let mut buffer = Vec::new();
+        {
+            let mut ser = Serializer::new(&mut buffer).with_indent(2);
+            assert!(ser.serialize_value(&(1, 2)).is_err());
+            for event in &events[..2] {
+                ser.serialize_value(event).unwrap();
+            }
+        }
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            concat!(
+                "<Event id=\"1\">\n  <message>started</message>\n</Event>\n",
+                "<Event id=\"2\">\n  <message>running</message>\n</Event>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_self_closing() {
+        #[derive(Serialize)]
+        struct Marker;
+
+        #[derive(Serialize)]
+        struct Nothing {}
+
+        #[derive(Serialize)]
+        struct Flags {
+            marker: Marker,
+            nothing: Nothing,
+        }
+
+        let flags = Flags {
+            marker: Marker,
+            nothing: Nothing {},
+        };
+        let inputs = vec![
+            (true, "<Marker/>", "<Nothing/>", "<Flags><marker/><nothing/></Flags>"),
+            (
+                false,
+                "<Marker></Marker>",
+                "<Nothing></Nothing>",
+                "<Flags><marker></marker><nothing></nothing></Flags>",
+            ),
+        ];
+
+        fn serialize<T: Serialize>(value: &T, self_closing: bool) -> String {
+            let mut buffer = Vec::new();
+            {
+                let mut ser = Serializer::new(&mut buffer).self_closing(self_closing);
+                value.serialize(&mut ser).unwrap();
+            }
+            String::from_utf8(buffer).unwrap()
+        }
+
+        for (enabled, marker, nothing, nested) in inputs {
+            assert_eq!(serialize(&Marker, enabled), marker);
+            assert_eq!(serialize(&Nothing {}, enabled), nothing);
+            assert_eq!(serialize(&flags, enabled), nested);
+        }
+    }
+
+    #[test]
+    fn test_self_closing_space() {
+        #[derive(Serialize)]
+        struct Marker;
+
+        #[derive(Serialize)]
+        struct Nothing {}
+
+        #[derive(Serialize)]
+        struct Point {
+            #[serde(rename = "@x")]
+            x: i32,
+        }
+
+        #[derive(Serialize)]
+        struct Flags {
+            marker: Marker,
+            name: String,
+            nothing: Nothing,
+            point: Point,
+        }
+
+        let flags = Flags {
+            marker: Marker,
+            name: String::new(),
+            nothing: Nothing {},
+            point: Point { x: 1 },
+        };
+        let inputs = vec![
+            (false, r#"<Flags><marker/><name/><nothing/><point x="1"/></Flags>"#),
+            (true, r#"<Flags><marker /><name /><nothing /><point x="1" /></Flags>"#),
+        ];
+
+        for (enabled, should_be) in inputs {
+            let mut buffer = Vec::new();
+            {
+                let mut ser = Serializer::new(&mut buffer).self_closing_space(enabled);
+                flags.serialize(&mut ser).unwrap();
+            }
+            assert_eq!(String::from_utf8(buffer).unwrap(), should_be);
+        }
+    }
+
+    #[test]
+    fn test_serialize_pretty() {
+        #[derive(Serialize)]
+        struct Point {
+            #[serde(rename = "@x")]
+            x: i32,
+            #[serde(rename = "@y")]
+            y: i32,
+        }
+
+        #[derive(Serialize)]
+        struct Shape {
+            name: String,
+            #[serde(rename = "point")]
+            points: Vec<Point>,
+            style: Style,
+        }
+
+        #[derive(Serialize)]
+        struct Style {
+            color: String,
+        }
+
+        let shape = Shape {
+            name: "line".to_string(),
+            points: vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }],
+            style: Style {
+                color: "red".to_string(),
+            },
+        };
+        let should_be = concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<Shape>\n",
+            "  <name>line</name>\n",
+            "  <point x=\"1\" y=\"2\"/>\n",
+            "  <point x=\"3\" y=\"4\"/>\n",
+            "  <style>\n",
+            "    <color>red</color>\n",
+            "  </style>\n",
+            "</Shape>"
+        );
+        let mut buffer = Vec::new();
+
+        {
+            let mut ser = Serializer::new(&mut buffer).with_indent(2).with_declaration(true);
+            shape.serialize(&mut ser).unwrap();
+        }
+
+        let got = String::from_utf8(buffer).unwrap();
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn test_attribute_per_line() {
+        #[derive(Serialize)]
+        struct Link {
+            #[serde(rename = "@href")]
+            href: String,
+            #[serde(rename = "@rel")]
+            rel: String,
+            text: String,
+        }
+
+        #[derive(Serialize)]
+        struct Page {
+            #[serde(rename = "link")]
+            links: Vec<Link>,
+        }
+
+        let page = Page {
+            links: vec![Link {
+                href: "/a?x=1&y=2".to_string(),
+                rel: "next".to_string(),
+                text: "Next".to_string(),
+            }],
+        };
+        let inputs = vec![
+            (
+                false,
+                concat!(
+                    "<Page>\n",
+                    "  <link href=\"/a?x=1&amp;y=2\" rel=\"next\">\n",
+                    "    <text>Next</text>\n",
+                    "  </link>\n",
+                    "</Page>"
+                ),
+            ),
+            (
+                true,
+                concat!(
+                    "<Page>\n",
+                    "  <link\n",
+                    "    href=\"/a?x=1&amp;y=2\"\n",
+                    "    rel=\"next\">\n",
+                    "    <text>Next</text>\n",
+                    "  </link>\n",
+                    "</Page>"
+                ),
+            ),
+        ];
+
+        for (enabled, should_be) in inputs {
+            let mut buffer = Vec::new();
+            {
+                let mut ser = Serializer::new(&mut buffer).with_indent(2).attribute_per_line(enabled);
+                page.serialize(&mut ser).unwrap();
+            }
+            assert_eq!(String::from_utf8(buffer).unwrap(), should_be);
+        }
+    }
+
+    #[test]
+    fn test_serialize_numeric_attributes() {
+        #[derive(Serialize)]
+        #[serde(rename = "server")]
+        struct Server {
+            #[serde(rename = "@port")]
+            port: u16,
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename = "limits")]
+        struct Limits {
+            #[serde(rename = "@a")]
+            a: i8,
+            #[serde(rename = "@b")]
+            b: i16,
+            #[serde(rename = "@c")]
+            c: i32,
+            #[serde(rename = "@d")]
+            d: i64,
+            #[serde(rename = "@e")]
+            e: u8,
+            #[serde(rename = "@f")]
+            f: u32,
+            #[serde(rename = "@g")]
+            g: u64,
+        }
+
+        assert_eq!(to_string(&Server { port: 8080 }).unwrap(), r#"<server port="8080"/>"#);
+
+        let limits = Limits {
+            a: i8::MIN,
+            b: -300,
+            c: i32::MAX,
+            d: i64::MIN,
+            e: u8::MAX,
+            f: 0,
+            g: u64::MAX,
+        };
+        assert_eq!(
+            to_string(&limits).unwrap(),
+            concat!(
+                r#"<limits a="-128" b="-300" c="2147483647" d="-9223372036854775808" "#,
+                r#"e="255" f="0" g="18446744073709551615"/>"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_serialize_optional_attributes() {
+        #[derive(Serialize)]
+        #[serde(rename = "link")]
+        struct Link {
+            #[serde(rename = "@title")]
+            title: Option<String>,
+        }
+
+        let inputs = vec![
+            (Some(""), r#"<link title=""/>"#),
+            (Some("x"), r#"<link title="x"/>"#),
+            (None, "<link/>"),
+        ];
+
+        for (title, should_be) in inputs {
+            let mut buffer = Vec::new();
+
+            {
+                let mut ser = Serializer::new(&mut buffer);
+                let link = Link {
+                    title: title.map(str::to_string),
+                };
+                link.serialize(&mut ser).unwrap();
+            }
+
+            let got = String::from_utf8(buffer).unwrap();
+            assert_eq!(got, should_be);
+        }
+    }
+
+    #[test]
+    fn test_serialize_optional_fields() {
+        #[derive(Serialize)]
+        struct S {
+            a: Option<i32>,
+            b: Option<i32>,
+        }
+
+        let inputs = vec![
+            (Some(1), None, "<S><a>1</a></S>"),
+            (None, Some(2), "<S><b>2</b></S>"),
+            (None, None, "<S/>"),
+        ];
+
+        for (a, b, should_be) in inputs {
+            assert_eq!(to_string(&S { a, b }).unwrap(), should_be);
+        }
+
+        // A field that writes nothing leaves the start tag open for attributes.
+        #[derive(Serialize)]
+        struct N {
+            a: Option<i32>,
+            #[serde(rename = "@id")]
+            id: u32,
+            b: Vec<i32>,
+        }
+
+        let n = N {
+            a: None,
+            id: 1,
+            b: vec![],
+        };
+        assert_eq!(to_string(&n).unwrap(), r#"<N id="1"/>"#);
+    }
+
+    #[test]
+    fn test_serialize_value_field() {
+        #[derive(Serialize)]
+        struct Name {
+            #[serde(rename = "@lang")]
+            lang: String,
+            #[serde(rename = "$value")]
+            text: String,
+        }
+
+        let name = Name {
+            lang: "en".to_string(),
+            text: "Fish & Chips".to_string(),
+        };
+        assert_eq!(to_string(&name).unwrap(), r#"<Name lang="en">Fish &amp; Chips</Name>"#);
+
+        let name = Name {
+            lang: "en".to_string(),
+            text: String::new(),
+        };
+        assert_eq!(to_string(&name).unwrap(), r#"<Name lang="en"/>"#);
+    }
+
+    #[test]
+    fn test_serialize_empty_seqs() {
+        #[derive(Serialize)]
+        struct Post {
+            title: String,
+            tag: Vec<String>,
+        }
+
+        let inputs = vec![
+            (vec![], false, "<Post><title>Hi</title></Post>"),
+            (vec![], true, "<Post><title>Hi</title><tag/></Post>"),
+            (vec!["a"], true, "<Post><title>Hi</title><tag>a</tag></Post>"),
+        ];
+
+        for (tags, empty_seq_element, should_be) in inputs {
+            let mut buffer = Vec::new();
+
+            {
+                let mut ser = Serializer::new(&mut buffer).empty_seq_element(empty_seq_element);
+                let post = Post {
+                    title: "Hi".to_string(),
+                    tag: tags.into_iter().map(str::to_string).collect(),
+                };
+                post.serialize(&mut ser).unwrap();
+            }
+
+            let got = String::from_utf8(buffer).unwrap();
+            assert_eq!(got, should_be);
+        }
+    }
+
+    #[test]
+    fn test_seq_item_tag() {
+        #[derive(Serialize)]
+        struct Post {
+            title: String,
+            tags: Vec<String>,
+        }
+
+        let inputs = vec![
+            (vec!["a", "b"], None, "<Post><title>Hi</title><tags>a</tags><tags>b</tags></Post>"),
+            (
+                vec!["a", "b"],
+                Some("item"),
+                "<Post><title>Hi</title><tags><item>a</item><item>b</item></tags></Post>",
+            ),
+            (vec![], Some("item"), "<Post><title>Hi</title><tags/></Post>"),
+        ];
+
+        for (tags, item_tag, should_be) in inputs {
+            let mut buffer = Vec::new();
+
+            {
+                let mut ser = Serializer::new(&mut buffer).seq_item_tag(item_tag);
+                let post = Post {
+                    title: "Hi".to_string(),
+                    tags: tags.into_iter().map(str::to_string).collect(),
+                };
+                post.serialize(&mut ser).unwrap();
+            }
+
+            let got = String::from_utf8(buffer).unwrap();
+            assert_eq!(got, should_be);
+        }
+
+        // An empty wrapper is an element without content like any other.
+        let post = Post {
+            title: "Hi".to_string(),
+            tags: vec![],
+        };
+        let inputs = vec![
+            (false, None, "<Post><title>Hi</title><tags></tags></Post>"),
+            (true, Some(2), "<Post>\n  <title>Hi</title>\n  <tags/>\n</Post>"),
+        ];
+
+        for (self_closing, indent, should_be) in inputs {
+            let mut buffer = Vec::new();
+            {
+                let mut ser = Serializer::new(&mut buffer).seq_item_tag(Some("i")).self_closing(self_closing);
+                if let Some(spaces) = indent {
+                    ser = ser.with_indent(spaces);
+                }
+                post.serialize(&mut ser).unwrap();
+            }
+            assert_eq!(String::from_utf8(buffer).unwrap(), should_be);
+        }
+    }
+
+    #[test]
+    fn test_serialize_skipped_fields() {
+        #[derive(Serialize)]
+        struct Person {
+            name: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            nickname: Option<String>,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            pets: Vec<String>,
+        }
+
+        let inputs = vec![
+            (
+                Person {
+                    name: "Bob".to_string(),
+                    nickname: None,
+                    pets: vec![],
+                },
+                "<Person><name>Bob</name></Person>",
+            ),
+            (
+                Person {
+                    name: "Bob".to_string(),
+                    nickname: Some("Bobby".to_string()),
+                    pets: vec!["Rex".to_string()],
+                },
+                "<Person><name>Bob</name><nickname>Bobby</nickname><pets>Rex</pets></Person>",
+            ),
+        ];
+
+        for (src, should_be) in inputs {
+            let mut buffer = Vec::new();
+
+            {
+                let mut ser = Serializer::new(&mut buffer);
+                src.serialize(&mut ser).unwrap();
+            }
+
+            let got = String::from_utf8(buffer).unwrap();
+            assert_eq!(got, should_be);
+        }
+    }
+
+    #[test]
+    fn test_serialize_text() {
+        let inputs = vec![
+            (None, "a < b", "a &lt; b"),
+            (Some(2), "a < b & c", "a &lt; b &amp; c"),
+            (Some(2), "a < b && c > d", "<![CDATA[a < b && c > d]]>"),
+            (Some(0), "x[y]]>z", "<![CDATA[x[y]]]]><![CDATA[>z]]>"),
+        ];
+
+        for (threshold, src, should_be) in inputs {
+            let mut buffer = Vec::new();
+
+            {
+                let mut ser = Serializer::new(&mut buffer);
+                if let Some(threshold) = threshold {
+                    ser = ser.auto_cdata(threshold);
+                }
+                ser.serialize_str(src).unwrap();
+            }
+
+            let got = String::from_utf8(buffer).unwrap();
+            assert_eq!(got, should_be);
+        }
+    }
+
+    #[test]
+    fn test_serialize_line_endings() {
+        let inputs = vec![
+            (LineEnding::Lf, "a\r\nb\nc\rd", "a\nb\nc\nd"),
+            (LineEnding::CrLf, "a\r\nb\nc\rd", "a\r\nb\r\nc\r\nd"),
+        ];
+
+        for (ending, src, should_be) in inputs {
+            let mut buffer = Vec::new();
+
+            {
+                let mut ser = Serializer::new(&mut buffer).line_endings(ending);
+                ser.serialize_str(src).unwrap();
+            }
+
+            let got = String::from_utf8(buffer).unwrap();
+            assert_eq!(got, should_be);
+        }
+    }
+
+    #[test]
+    fn test_serialize_map_entries() {
+        let should_be = "<name>Bob</name><age>5</age>";
+        let mut buffer = Vec::new();
+
+        {
+            let mut ser = Serializer::new(&mut buffer);
+            let mut map = Map::new(&mut ser);
+            map.serialize_entry("name", "Bob").unwrap();
+            map.serialize_entry("age", "5").unwrap();
+        }
+
+        let got = String::from_utf8(buffer).unwrap();
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn test_serialize_map_keys_and_values() {
+        let mut buffer = Vec::new();
+
+        {
+            let mut ser = Serializer::new(&mut buffer);
+            let mut map = Map::new(&mut ser);
+            map.serialize_key("name").unwrap();
+            map.serialize_value("Bob").unwrap();
+            map.serialize_key(&5).unwrap();
+            assert!(map.serialize_value("five").is_err());
+            assert!(map.serialize_value("Bob").is_err());
+        }
+
+        let got = String::from_utf8(buffer).unwrap();
+        assert_eq!(got, "<name>Bob</name>");
+    }
+
+    #[test]
+    fn test_serialize_invalid_map_keys() {
+        let inputs = vec![
+            ("name", false, Some("<name>Bob</name>")),
+            ("first name", false, None),
+            ("1st", false, None),
+            ("xlink:href", false, Some("<xlink:href>Bob</xlink:href>")),
+            ("xmlns:x", false, Some("<xmlns:x>Bob</xmlns:x>")),
+            (":name", false, None),
+            ("name:", false, None),
+            ("a:b:c", false, None),
+            ("a::b", false, None),
+            ("name", true, Some("<name>Bob</name>")),
+            ("first name", true, Some(r#"<entry key="first name">Bob</entry>"#)),
+            ("a\"b", true, Some(r#"<entry key="a&quot;b">Bob</entry>"#)),
+        ];
+
+        for (key, fallback, should_be) in inputs {
+            let mut values = BTreeMap::new();
+            values.insert(key, "Bob");
+            let mut buffer = Vec::new();
+
+            let result = {
+                let mut ser = Serializer::new(&mut buffer).entry_fallback(fallback);
+                values.serialize(&mut ser)
+            };
+
+            match should_be {
+                Some(should_be) => {
+                    result.unwrap();
+                    assert_eq!(String::from_utf8(buffer).unwrap(), should_be);
+                },
+                None => assert!(result.is_err()),
+            }
+        }
+
+        assert_eq!(to_string_with_root(&5, "svg:width").unwrap(), "<svg:width>5</svg:width>");
+        assert!(to_string_with_root(&5, "svg:").is_err());
+    }
+
+    #[test]
+    fn test_serialize_with_root() {
+        #[derive(Serialize)]
+        struct Person {
+            name: String,
+        }
+
+        let mut settings = BTreeMap::new();
+        settings.insert("k1", "v1");
+        settings.insert("k2", "a \"quoted\" value");
+        assert_eq!(
+            to_string_with_root(&settings, "config").unwrap(),
+            r#"<config k1="v1" k2="a &quot;quoted&quot; value"/>"#
+        );
+
+        let empty: BTreeMap<String, String> = BTreeMap::new();
+        assert_eq!(to_string_with_root(&empty, "config").unwrap(), "<config/>");
+
+        let person = Person {
+            name: "Bob".to_string(),
+        };
+        assert_eq!(
+            to_string_with_root(&person, "user").unwrap(),
+            "<user><name>Bob</name></user>"
+        );
+
+        let mut nested = BTreeMap::new();
+        nested.insert("k1", vec![1, 2]);
+        assert!(to_string_with_root(&nested, "config").is_err());
+        assert!(to_string_with_root(&settings, "1config").is_err());
+        assert!(to_string_with_root(&settings, "my config").is_err());
+    }
+
+    #[test]
+    fn test_serialize_forest() {
+        #[derive(Serialize)]
+        struct Record {
+            id: u32,
+        }
+
+        let records = vec![
+            ("user".to_string(), Record { id: 1 }),
+            ("group".to_string(), Record { id: 2 }),
+        ];
+        assert_eq!(
+            to_string_forest(records.iter().map(|(name, record)| (name, record))).unwrap(),
+            "<user><id>1</id></user><group><id>2</id></group>"
+        );
+        assert_eq!(
+            to_string_forest(records).unwrap(),
+            "<user><id>1</id></user><group><id>2</id></group>"
+        );
+
+        assert!(to_string_forest(vec![("not valid", 1)]).is_err());
+    }
+
+    #[test]
+    fn test_serialize_enum() {
+        #[derive(Serialize)]
+        #[allow(dead_code)]
+        enum Node {
+            Boolean(bool),
+            Number(f64),
+            String(String),
+        }
+
+        let mut buffer = Vec::new();
+        let should_be = "<Boolean>true</Boolean>";
+
+        {
+            let mut ser = Serializer::new(&mut buffer);
+            let node = Node::Boolean(true);
+            node.serialize(&mut ser).unwrap();
+        }
+
+        let got = String::from_utf8(buffer).unwrap();
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn test_serialize_map_of_sequences() {
+        let mut values = BTreeMap::new();
+        values.insert("a", vec![1, 2]);
+        values.insert("b", vec![3]);
+
+        let should_be = "<a>1</a><a>2</a><b>3</b>";
+        let mut buffer = Vec::new();
+
+        {
+            let mut ser = Serializer::new(&mut buffer);
+            values.serialize(&mut ser).unwrap();
+        }
+
+        let got = String::from_utf8(buffer).unwrap();
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn serialize_a_list() {
+        let inputs = vec![1, 2, 3, 4];
+
+        let mut buffer = Vec::new();
+
+        {
+            let mut ser = Serializer::new(&mut buffer);
+            assert!(inputs.serialize(&mut ser).is_err());
+        }
+
+        assert_eq!(
+            to_string_with_root(&inputs, "n").unwrap(),
+            "<n>1</n><n>2</n><n>3</n><n>4</n>"
+        );
     }
 }
@@ -1,9 +1,11 @@
 use std::io::Write;
 
 use serde::ser::{self, Serialize};
+use xml::escape::escape_str_attribute;
 
 use ser::Serializer;
-use error::{Error, Result};
+use ser::attr::AttrValueSerializer;
+use error::{Error, ErrorKind, Result};
 
 /// An implementation of `SerializeMap` for serializing to XML.
 pub struct Map<'w, W>
@@ -11,6 +13,10 @@ where
     W: 'w + Write,
 {
     parent: &'w mut Serializer<W>,
+    tag: Option<String>,
+    attributes: bool,
+    /// The key given to `serialize_key`, waiting for its value.
+    pending_key: Option<String>,
 }
 
 impl<'w, W> Map<'w, W>
@@ -18,7 +24,62 @@ where
     W: 'w + Write,
 {
     pub fn new(parent: &'w mut Serializer<W>) -> Map<'w, W> {
-        Map { parent }
+        Map {
+            parent,
+            tag: None,
+            attributes: false,
+            pending_key: None,
+        }
+    }
+
+    /// A map whose entries are enclosed in an already opened `<tag>` element.
+    pub fn wrapped(parent: &'w mut Serializer<W>, tag: String) -> Map<'w, W> {
+        Map {
+            parent,
+            tag: Some(tag),
+            attributes: false,
+            pending_key: None,
+        }
+    }
+
+    /// A map whose entries are written as the attributes of a `<tag` start tag that has
+    /// been written up to, but not including, its end.
+    pub fn attributes(parent: &'w mut Serializer<W>, tag: String) -> Map<'w, W> {
+        Map {
+            parent,
+            tag: Some(tag),
+            attributes: true,
+            pending_key: None,
+        }
+    }
+
+    fn write_entry<V: ?Sized + Serialize>(&mut self, key: String, value: &V) -> Result<()> {
+        if self.attributes {
+            if !is_valid_name(&key) {
+                return Err(ErrorKind::Custom(
+                    format!("map key `{}` is not a valid XML attribute name", key),
+                ).into());
+            }
+            if let Some(value) = value.serialize(self.parent.attr_value_serializer())? {
+                self.parent.write_attribute(&key, &value)?;
+            }
+            return Ok(());
+        }
+        if is_valid_name(&key) {
+            self.parent.set_pending_tag(key);
+            return value.serialize(&mut *self.parent);
+        }
+        if !self.parent.entry_fallback {
+            return Err(ErrorKind::Custom(
+                format!("map key `{}` is not a valid XML element name", key),
+            ).into());
+        }
+        self.parent.start_element()?;
+        write!(self.parent.writer, "<entry key=\"{}\">", escape_str_attribute(&key))?;
+        value.serialize(&mut *self.parent)?;
+        self.parent.end_element()?;
+        write!(self.parent.writer, "</entry>")?;
+        Ok(())
     }
 }
 
@@ -29,15 +90,26 @@ where
     type Ok = ();
     type Error = Error;
 
-    fn serialize_key<T: ?Sized + Serialize>(&mut self, _: &T) -> Result<()> {
-        panic!("impossible to serialize the key on its own, please use serialize_entry()")
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        self.pending_key = Some(map_key(key)?);
+        Ok(())
     }
 
     fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
-        value.serialize(&mut *self.parent)
+        match self.pending_key.take() {
+            Some(key) => self.write_entry(key, value),
+            None => Err(ErrorKind::Custom("map value without a key".to_string()).into()),
+        }
     }
 
     fn end(self) -> Result<Self::Ok> {
+        if self.attributes {
+            let tag = self.tag.unwrap_or_default();
+            self.parent.end_empty_element(&tag)?;
+        } else if let Some(tag) = self.tag {
+            self.parent.end_element()?;
+            write!(self.parent.writer, "</{}>", tag)?;
+        }
         Ok(())
     }
 
@@ -46,36 +118,255 @@ where
         key: &K,
         value: &V,
     ) -> Result<()> {
-        // TODO: Is it possible to ensure our key is never a composite type?
-        // Anything which isn't a "primitive" would lead to malformed XML here...
-        write!(self.parent.writer, "<")?;
-        key.serialize(&mut *self.parent)?;
-        write!(self.parent.writer, ">")?;
+        let key = map_key(key)?;
+        self.write_entry(key, value)
+    }
+}
 
-        value.serialize(&mut *self.parent)?;
+fn map_key<K: ?Sized + Serialize>(key: &K) -> Result<String> {
+    match key.serialize(AttrValueSerializer::default())? {
+        Some(key) => Ok(key),
+        None => Err(ErrorKind::Custom("map key is missing".to_string()).into()),
+    }
+}
+
+/// Whether `name` can be used as an element or attribute name: a local name, or a
+/// prefix and a local name separated by one `:`, as in `xlink:href`. Each part starts
+/// with a letter or `_` and continues with letters, digits, `-`, `_` and `.`.
+pub fn is_valid_name(name: &str) -> bool {
+    let mut parts = name.splitn(2, ':');
+    parts.all(|part| {
+        let mut chars = part.chars();
+        match chars.next() {
+            Some(first) if first.is_alphabetic() || first == '_' => {},
+            _ => return false,
+        }
+        chars.all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.')
+    })
+}
 
-        write!(self.parent.writer, "</")?;
-        key.serialize(&mut *self.parent)?;
-        write!(self.parent.writer, ">")?;
+/// An implementation of `SerializeSeq` for serializing to XML.
+///
+/// Every element is written as its own sibling element named after the enclosing
+/// struct field or map key, which is the shape the deserializer reads back into a
+/// sequence. At the document root there is no such name, so each element is written
+/// under its own name instead (a struct as `<StructName>`), producing a forest of
+/// root elements. A scalar has no name of its own, so it can't be an element there.
+/// With `Serializer::seq_item_tag`, a named sequence is instead written inside one
+/// element of that name, and every element is named after the item tag. That element
+/// is written without content, e.g. as `<tags/>`, when the sequence is empty.
+pub struct Seq<'w, W>
+where
+    W: 'w + Write,
+{
+    parent: &'w mut Serializer<W>,
+    tag: Option<String>,
+    /// An element opened around the sequence, to be closed after it.
+    wrapper: Option<String>,
+    /// Whether the wrapper's start tag is still missing its `>`, as nothing has been
+    /// written into it yet.
+    start_tag_open: bool,
+    empty: bool,
+}
+
+impl<'w, W> Seq<'w, W>
+where
+    W: 'w + Write,
+{
+    pub fn new(parent: &'w mut Serializer<W>, tag: Option<String>) -> Seq<'w, W> {
+        Seq {
+            parent,
+            tag,
+            wrapper: None,
+            start_tag_open: false,
+            empty: true,
+        }
+    }
+
+    /// Close the `<wrapper` element, whose start tag has been written up to, but not
+    /// including, its `>`, after the sequence.
+    pub fn wrapped_in(mut self, wrapper: Option<String>) -> Seq<'w, W> {
+        self.start_tag_open = wrapper.is_some();
+        self.wrapper = wrapper;
+        self
+    }
+}
+
+impl<'w, W> ser::SerializeSeq for Seq<'w, W>
+where
+    W: 'w + Write,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.empty = false;
+        match self.tag {
+            Some(ref tag) => self.parent.set_pending_tag(tag.clone()),
+            None => self.parent.untagged_item = true,
+        }
+        if self.wrapper.is_none() {
+            let result = value.serialize(&mut *self.parent);
+            self.parent.untagged_item = false;
+            return result;
+        }
+        // The wrapper's start tag is closed by the first child element written, if any.
+        self.parent.unclosed_start_tag = self.start_tag_open;
+        let result = value.serialize(&mut *self.parent);
+        self.start_tag_open = ::std::mem::replace(&mut self.parent.unclosed_start_tag, false);
+        self.parent.untagged_item = false;
+        result
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        if self.empty && self.wrapper.is_none() && self.parent.empty_seq_element {
+            if let Some(tag) = self.tag {
+                self.parent.start_element()?;
+                write!(self.parent.writer, "<{}", tag)?;
+                self.parent.end_empty_element(&tag)?;
+            }
+        }
+        match self.wrapper {
+            Some(wrapper) if self.start_tag_open => self.parent.end_empty_element(&wrapper)?,
+            Some(wrapper) => {
+                self.parent.end_element()?;
+                write!(self.parent.writer, "</{}>", wrapper)?;
+            },
+            None => {},
+        }
         Ok(())
     }
 }
 
+/// A tuple variant is written as a sequence of elements named after the variant,
+/// which is how the deserializer reads a tuple.
+impl<'w, W> ser::SerializeTupleVariant for Seq<'w, W>
+where
+    W: 'w + Write,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// An implementation of `SerializeTupleStruct` for serializing to XML, which writes
+/// the fields as the attributes `v0`, `v1`, ... of an empty element.
+///
+/// The deserializer reads attributes into a tuple by position, so this shape reads
+/// back into the same tuple struct when it is the value of a field.
+pub struct AttrTuple<'w, W>
+where
+    W: 'w + Write,
+{
+    parent: &'w mut Serializer<W>,
+    tag: String,
+    index: usize,
+}
+
+impl<'w, W> AttrTuple<'w, W>
+where
+    W: 'w + Write,
+{
+    /// A tuple struct whose start tag has been written up to, but not including, its
+    /// `/>`.
+    pub fn new(parent: &'w mut Serializer<W>, tag: String) -> AttrTuple<'w, W> {
+        AttrTuple {
+            parent,
+            tag,
+            index: 0,
+        }
+    }
+}
+
+impl<'w, W> ser::SerializeTupleStruct for AttrTuple<'w, W>
+where
+    W: 'w + Write,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        if let Some(value) = value.serialize(self.parent.attr_value_serializer())? {
+            self.parent.write_attribute(&format!("v{}", self.index), &value)?;
+        }
+        self.index += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.parent.end_empty_element(&self.tag)
+    }
+}
+
 /// An implementation of `SerializeStruct` for serializing to XML.
+///
+/// Fields renamed with a leading `@` (e.g. `#[serde(rename = "@id")]`) are written as
+/// attributes of the struct's element. They must all come before the first child
+/// element, as the start tag is closed as soon as one is written. A struct with no
+/// child elements, including one whose other fields all write nothing, is written as
+/// a self-closing tag. A field renamed to `$value` is written as the element's
+/// content, e.g. as text or as one element per item of a sequence.
 pub struct Struct<'w, W>
 where
     W: 'w + Write,
 {
     parent: &'w mut Serializer<W>,
-    name: &'w str,
+    name: String,
+    start_tag_open: bool,
+    /// An element opened around the struct's own, to be closed after it.
+    wrapper: Option<String>,
 }
 
 impl<'w, W> Struct<'w, W>
 where
     W: 'w + Write,
 {
-    pub fn new(parent: &'w mut Serializer<W>, name: &'w str) -> Struct<'w, W> {
-        Struct { parent, name }
+    /// A struct whose start tag has already been written in full.
+    pub fn new(parent: &'w mut Serializer<W>, name: &str) -> Struct<'w, W> {
+        Struct {
+            parent,
+            name: name.to_string(),
+            start_tag_open: false,
+            wrapper: None,
+        }
+    }
+
+    /// A struct whose start tag has been written up to, but not including, its `>`, so
+    /// that attributes can still be added.
+    pub fn open(parent: &'w mut Serializer<W>, name: &str) -> Struct<'w, W> {
+        Struct {
+            parent,
+            name: name.to_string(),
+            start_tag_open: true,
+            wrapper: None,
+        }
+    }
+
+    /// Close the already opened `<wrapper>` element after the struct's own element.
+    pub fn wrapped_in(mut self, wrapper: Option<String>) -> Struct<'w, W> {
+        self.wrapper = wrapper;
+        self
+    }
+
+    fn serialize_attribute<T: ?Sized + Serialize>(&mut self, key: &str, value: &T) -> Result<()> {
+        if !self.start_tag_open {
+            return Err(ErrorKind::Custom(format!(
+                "attribute `{}` of <{}> must be serialized before any child elements",
+                key,
+                self.name
+            )).into());
+        }
+        if let Some(value) = value.serialize(self.parent.attr_value_serializer())? {
+            self.parent.write_attribute(key, &value)?;
+        }
+        Ok(())
     }
 }
 
@@ -91,13 +382,52 @@ where
         key: &'static str,
         value: &T,
     ) -> Result<()> {
-        write!(self.parent.writer, "<{}>", key)?;
+        if let Some(attribute) = key.strip_prefix('@') {
+            return self.serialize_attribute(attribute, value);
+        }
+        // The start tag is closed by the first child element or text the field writes,
+        // if any. A `$value` field is the content of the struct's own element, without
+        // an element of its own.
+        self.parent.unclosed_start_tag = self.start_tag_open;
+        if key != "$value" {
+            self.parent.set_pending_tag(key.to_string());
+        }
         value.serialize(&mut *self.parent)?;
-        write!(self.parent.writer, "</{}>", key)?;
+        self.start_tag_open = ::std::mem::replace(&mut self.parent.unclosed_start_tag, false);
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok> {
-        write!(self.parent.writer, "</{}>", self.name).map_err(|e| e.into())
+        if self.start_tag_open {
+            self.parent.end_empty_element(&self.name)?;
+        } else {
+            self.parent.end_element()?;
+            write!(self.parent.writer, "</{}>", self.name)?;
+        }
+        if let Some(wrapper) = self.wrapper {
+            self.parent.end_element()?;
+            write!(self.parent.writer, "</{}>", wrapper)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'w, W> ser::SerializeStructVariant for Struct<'w, W>
+where
+    W: 'w + Write,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeStruct::end(self)
     }
 }
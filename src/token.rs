@@ -0,0 +1,42 @@
+//! Shared plumbing for the with-helpers that ask our deserializer for a string
+//! through a newtype struct token, like `inner_text` and `cdata`.
+
+use std::fmt;
+
+use serde::{de, Deserialize, Deserializer};
+
+/// Deserialize the string `serde_xml_rs::Deserializer` produces for `token`.
+///
+/// Deserializers other than ours don't know the token and hand over the content as
+/// is, which is read as a plain string. `expecting` describes the value in errors.
+pub fn deserialize_token_string<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    token: &'static str,
+    expecting: &'static str,
+) -> Result<String, D::Error> {
+    deserializer.deserialize_newtype_struct(token, TokenStringVisitor { expecting })
+}
+
+struct TokenStringVisitor {
+    expecting: &'static str,
+}
+
+impl<'de> de::Visitor<'de> for TokenStringVisitor {
+    type Value = String;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(self.expecting)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<String, E> {
+        Ok(value.to_string())
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<String, E> {
+        Ok(value)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<String, D::Error> {
+        String::deserialize(deserializer)
+    }
+}
@@ -0,0 +1,21 @@
+//! `xsd:date` text (`YYYY-MM-DD`) for `chrono::NaiveDate` values, for use with
+//! `#[serde(with = "serde_xml_rs::xsd_date")]`.
+//!
+//! Unlike `serde_xml_rs::datetime`, this reads and writes a calendar date on its own,
+//! without a time of day or an offset.
+
+use chrono::NaiveDate;
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+const FORMAT: &str = "%Y-%m-%d";
+
+pub fn serialize<S: Serializer>(value: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&value.format(FORMAT))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDate, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    NaiveDate::parse_from_str(&text, FORMAT).map_err(|err| {
+        de::Error::custom(format_args!("`{}` is not an xsd:date (YYYY-MM-DD): {}", text, err))
+    })
+}
@@ -0,0 +1,57 @@
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_xml_rs;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::Serialize;
+use serde_xml_rs::Serializer;
+
+/// Counts the bytes allocated by the whole test binary, which only holds one test.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+#[derive(Serialize)]
+struct Page<'a> {
+    title: &'a str,
+    body: Cow<'a, str>,
+}
+
+#[test]
+fn borrowed_text_is_not_copied() {
+    let body = "lorem ipsum ".repeat(100_000);
+    let page = Page {
+        title: "Lorem",
+        body: Cow::Borrowed(&body),
+    };
+    let mut buffer = Vec::with_capacity(body.len() + 1024);
+
+    let before = ALLOCATED.load(Ordering::SeqCst);
+    {
+        let mut ser = Serializer::new(&mut buffer);
+        page.serialize(&mut ser).unwrap();
+    }
+    let allocated = ALLOCATED.load(Ordering::SeqCst) - before;
+
+    // Only element names are allocated, nothing in proportion to the text.
+    assert!(allocated < 1024, "allocated {} bytes", allocated);
+    assert_eq!(buffer.len(), "<Page><title>Lorem</title><body></body></Page>".len() + body.len());
+}
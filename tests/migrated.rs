@@ -84,10 +84,10 @@ where
     T: PartialEq + Debug + ser::Serialize + de::Deserialize<'de>,
 {
     for &s in errors {
-        assert!(match from_str::<T>(s) {
-            Err(Error(ErrorKind::Syntax(_), _)) => true,
-            _ => false,
-        });
+        assert!(matches!(
+            from_str::<T>(s),
+            Err(Error(ErrorKind::Syntax(_), _))
+        ));
     }
 }
 
@@ -338,6 +338,26 @@ fn test_parse_u64() {
     ]);
 }
 
+#[test]
+fn test_parse_i128() {
+    init_logger();
+    test_parse_ok(&[
+        ("<bla>-2</bla>", -2i128),
+        ("<bla>-18446744073709551616</bla>", -(1i128 << 64)),
+        ("<bla>170141183460469231731687303715884105727</bla>", i128::MAX),
+    ]);
+}
+
+#[test]
+fn test_parse_u128() {
+    init_logger();
+    test_parse_ok(&[
+        ("<bla>0</bla>", 0u128),
+        ("<bla>18446744073709551616</bla>", 1u128 << 64),
+        ("<bla> 340282366920938463463374607431768211455 </bla>", u128::MAX),
+    ]);
+}
+
 #[test]
 fn test_parse_bool() {
     test_parse_ok(&[
@@ -345,6 +365,10 @@ fn test_parse_bool() {
         ("<bla>false</bla>", false),
         ("<bla> true </bla>", true),
         ("<bla> false </bla>", false),
+        ("<bla>1</bla>", true),
+        ("<bla>0</bla>", false),
+        ("<bla>yes</bla>", true),
+        ("<bla>no</bla>", false),
     ]);
 }
 
@@ -600,7 +624,7 @@ fn test_hugo_duncan2() {
             struct Helper<U> {
                 item: Vec<U>,
             }
-            let h: Helper<_> = try!(de::Deserialize::deserialize(deserializer));
+            let h: Helper<_> = de::Deserialize::deserialize(deserializer)?;
             Ok(ItemVec(h.item))
         }
     }
@@ -948,13 +972,15 @@ fn unknown_field() {
 
 #[test]
 fn test_parse_unfinished() {
-    test_parse_err::<Simple>(&[
-        "<Simple>
+    let s = "<Simple>
             <c>abc</c>
             <a/>
             <b>2</b>
-            <d/>",
-    ]);
+            <d/>";
+    assert!(matches!(
+        from_str::<Simple>(s),
+        Err(Error(ErrorKind::UnexpectedEof(_), _))
+    ));
 }
 
 #[test]
@@ -1021,12 +1047,12 @@ fn futile2() {
     #[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
     struct Object {
         field: Option<Null>,
-    };
+    }
 
     #[derive(Eq, PartialEq, Debug, Serialize, Deserialize)]
     struct Stuff {
         stuff_field: Option<Object>,
-    };
+    }
 
     test_parse_ok(&[
         (
@@ -1,8 +1,20 @@
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "rust_decimal")]
+extern crate rust_decimal;
+extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_xml_rs;
 
-use serde_xml_rs::{from_str, to_string};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_xml_rs::{from_reader, from_str, to_string, to_writer, Deserializer, RawXml, Serializer};
 
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -13,6 +25,7 @@ struct Item {
 
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 enum Node {
     Boolean(bool),
     Identifier { value: String, index: u32 },
@@ -43,7 +56,608 @@ fn basic_struct() {
 
 
 #[test]
-#[ignore]
+fn writer_and_reader() {
+    let item = Item {
+        name: "Banana".to_string(),
+        source: "Store".to_string(),
+    };
+
+    let mut buffer = Vec::new();
+    to_writer(&mut buffer, &item).unwrap();
+
+    let deserialized_item: Item = from_reader(buffer.as_slice()).unwrap();
+    assert_eq!(deserialized_item, item);
+}
+
+
+#[test]
+fn root_sequence() {
+    let items = vec![
+        Item {
+            name: "Banana".to_string(),
+            source: "Store".to_string(),
+        },
+        Item {
+            name: "Apple".to_string(),
+            source: "Tree".to_string(),
+        },
+    ];
+    let should_be = "<Item><name>Banana</name><source>Store</source></Item><Item><name>Apple</name><source>Tree</source></Item>";
+
+    let serialized_items = to_string(&items).unwrap();
+    assert_eq!(serialized_items, should_be);
+
+    let deserialized_items: Vec<Item> = from_str(&serialized_items).unwrap();
+    assert_eq!(deserialized_items, items);
+}
+
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+enum Numbers {
+    Many(Vec<i32>),
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Holder {
+    numbers: Numbers,
+}
+
+#[test]
+fn newtype_variant_with_sequence() {
+    let numbers = Numbers::Many(vec![1, 2, 3]);
+
+    let serialized_numbers = to_string(&numbers).unwrap();
+    assert_eq!(serialized_numbers, "<Many><item>1</item><item>2</item><item>3</item></Many>");
+
+    let deserialized_numbers: Numbers = from_str(&serialized_numbers).unwrap();
+    assert_eq!(deserialized_numbers, numbers);
+
+    let holder = Holder { numbers };
+
+    let serialized_holder = to_string(&holder).unwrap();
+    assert_eq!(
+        serialized_holder,
+        "<Holder><numbers><Many><item>1</item><item>2</item><item>3</item></Many></numbers></Holder>"
+    );
+
+    let deserialized_holder: Holder = from_str(&serialized_holder).unwrap();
+    assert_eq!(deserialized_holder, holder);
+
+    let empty = Holder { numbers: Numbers::Many(vec![]) };
+
+    let serialized_empty = to_string(&empty).unwrap();
+    assert_eq!(serialized_empty, "<Holder><numbers><Many/></numbers></Holder>");
+
+    let deserialized_empty: Holder = from_str(&serialized_empty).unwrap();
+    assert_eq!(deserialized_empty, empty);
+}
+
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Shared {
+    item: Rc<Item>,
+    label: Arc<String>,
+}
+
+#[test]
+fn shared_pointers() {
+    let src = r#"<Shared><item><name>Banana</name><source>Store</source></item><label>fruit</label></Shared>"#;
+    let should_be = Shared {
+        item: Rc::new(Item {
+            name: "Banana".to_string(),
+            source: "Store".to_string(),
+        }),
+        label: Arc::new("fruit".to_string()),
+    };
+
+    let shared: Shared = from_str(src).unwrap();
+    assert_eq!(shared, should_be);
+
+    let reserialized_shared = to_string(&shared).unwrap();
+    assert_eq!(src, reserialized_shared);
+
+    let item = Rc::new(Item {
+        name: "Banana".to_string(),
+        source: "Store".to_string(),
+    });
+    let deserialized_item: Rc<Item> = from_str(&to_string(&item).unwrap()).unwrap();
+    assert_eq!(deserialized_item, item);
+}
+
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Groups {
+    groups: BTreeMap<String, Vec<i32>>,
+}
+
+#[test]
+fn map_of_sequences() {
+    let mut groups = BTreeMap::new();
+    groups.insert("odd".to_string(), vec![1, 3, 5]);
+    groups.insert("even".to_string(), vec![2]);
+    let groups = Groups { groups };
+
+    let serialized_groups = to_string(&groups).unwrap();
+    assert_eq!(
+        serialized_groups,
+        "<Groups><groups><even>2</even><odd>1</odd><odd>3</odd><odd>5</odd></groups></Groups>"
+    );
+
+    let deserialized_groups: Groups = from_str(&serialized_groups).unwrap();
+    assert_eq!(deserialized_groups, groups);
+}
+
+
+#[test]
+fn nested_maps() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Settings {
+        sections: BTreeMap<String, BTreeMap<String, String>>,
+    }
+
+    let mut network = BTreeMap::new();
+    network.insert("host".to_string(), "localhost".to_string());
+    network.insert("port".to_string(), "80".to_string());
+    let mut display = BTreeMap::new();
+    display.insert("theme".to_string(), "dark".to_string());
+    let mut sections = BTreeMap::new();
+    sections.insert("network".to_string(), network);
+    sections.insert("display".to_string(), display);
+    let settings = Settings { sections };
+
+    let serialized_settings = to_string(&settings).unwrap();
+    assert_eq!(
+        serialized_settings,
+        concat!(
+            "<Settings><sections>",
+            "<display><theme>dark</theme></display>",
+            "<network><host>localhost</host><port>80</port></network>",
+            "</sections></Settings>"
+        )
+    );
+
+    let deserialized_settings: Settings = from_str(&serialized_settings).unwrap();
+    assert_eq!(deserialized_settings, settings);
+}
+
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+enum Figure {
+    Empty,
+    Circle(u32),
+    Rect { width: u32, height: u32 },
+    Labelled { text: String, inner: Box<Figure> },
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Canvas {
+    name: String,
+    figure: Figure,
+}
+
+#[test]
+fn externally_tagged_enums() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    enum Value {
+        Missing,
+        Count(u32),
+        Range(u32, u32),
+        Named { name: String },
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Reading {
+        value: Value,
+    }
+
+    let inputs = vec![
+        (Value::Missing, "<Reading><value><Missing/></value></Reading>"),
+        (Value::Count(5), "<Reading><value><Count>5</Count></value></Reading>"),
+        (
+            Value::Range(1, 2),
+            "<Reading><value><Range>1</Range><Range>2</Range></value></Reading>",
+        ),
+        (
+            Value::Named { name: "x".to_string() },
+            "<Reading><value><Named><name>x</name></Named></value></Reading>",
+        ),
+    ];
+
+    for (value, should_be) in inputs {
+        let reading = Reading { value };
+        let serialized_reading = to_string(&reading).unwrap();
+        assert_eq!(serialized_reading, should_be);
+
+        let deserialized_reading: Reading = from_str(&serialized_reading).unwrap();
+        assert_eq!(deserialized_reading, reading);
+    }
+
+    assert_eq!(to_string(&Value::Missing).unwrap(), "<Missing/>");
+    assert_eq!(to_string(&Value::Count(5)).unwrap(), "<Count>5</Count>");
+}
+
+
+#[test]
+fn enums_inside_structs() {
+    let inputs = vec![
+        (Figure::Empty, "<figure><Empty/></figure>"),
+        (Figure::Circle(5), "<figure><Circle>5</Circle></figure>"),
+        (
+            Figure::Rect {
+                width: 3,
+                height: 4,
+            },
+            "<figure><Rect><width>3</width><height>4</height></Rect></figure>",
+        ),
+        (
+            Figure::Labelled {
+                text: "dot".to_string(),
+                inner: Box::new(Figure::Circle(1)),
+            },
+            "<figure><Labelled><text>dot</text><inner><Circle>1</Circle></inner></Labelled></figure>",
+        ),
+    ];
+
+    for (figure, should_be) in inputs {
+        let canvas = Canvas {
+            name: "main".to_string(),
+            figure,
+        };
+
+        let serialized_canvas = to_string(&canvas).unwrap();
+        assert_eq!(
+            serialized_canvas,
+            format!("<Canvas><name>main</name>{}</Canvas>", should_be)
+        );
+
+        let deserialized_canvas: Canvas = from_str(&serialized_canvas).unwrap();
+        assert_eq!(deserialized_canvas, canvas);
+    }
+}
+
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Point {
+    #[serde(rename(serialize = "@x", deserialize = "x"))]
+    x: i32,
+    #[serde(rename(serialize = "@y", deserialize = "y"))]
+    y: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Polygon {
+    point: Vec<Point>,
+}
+
+#[test]
+fn sequence_of_attribute_structs() {
+    let polygon = Polygon {
+        point: vec![Point { x: 0, y: 0 }, Point { x: 4, y: 0 }, Point { x: 4, y: -3 }],
+    };
+    let should_be = r#"<Polygon><point x="0" y="0"/><point x="4" y="0"/><point x="4" y="-3"/></Polygon>"#;
+
+    let serialized_polygon = to_string(&polygon).unwrap();
+    assert_eq!(serialized_polygon, should_be);
+
+    let deserialized_polygon: Polygon = from_str(&serialized_polygon).unwrap();
+    assert_eq!(deserialized_polygon, polygon);
+
+    let points = vec![Point { x: 1, y: 2 }];
+    assert_eq!(to_string(&points).unwrap(), r#"<Point x="1" y="2"/>"#);
+}
+
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Readings {
+    value: Vec<Option<i32>>,
+}
+
+#[test]
+fn sequence_of_options_with_xsi_nil() {
+    let readings = Readings {
+        value: vec![Some(1), None, Some(3)],
+    };
+    let should_be = concat!(
+        "<Readings><value>1</value>",
+        r#"<value xsi:nil="true" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"/>"#,
+        "<value>3</value></Readings>"
+    );
+
+    let mut buffer = Vec::new();
+    {
+        let mut ser = Serializer::new(&mut buffer).xsi_nil(true);
+        readings.serialize(&mut ser).unwrap();
+    }
+    let serialized_readings = String::from_utf8(buffer).unwrap();
+    assert_eq!(serialized_readings, should_be);
+
+    let deserialized_readings: Readings = from_str(&serialized_readings).unwrap();
+    assert_eq!(deserialized_readings, readings);
+}
+
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Snippet {
+    title: String,
+    #[serde(with = "serde_xml_rs::cdata")]
+    code: String,
+}
+
+#[test]
+fn cdata_field() {
+    let snippet = Snippet {
+        title: "if".to_string(),
+        code: "if a < b {}".to_string(),
+    };
+    let should_be = "<Snippet><title>if</title><code><![CDATA[if a < b {}]]></code></Snippet>";
+
+    let serialized_snippet = to_string(&snippet).unwrap();
+    assert_eq!(serialized_snippet, should_be);
+
+    let mut de = Deserializer::new_from_reader(serialized_snippet.as_bytes()).distinguish_cdata(true);
+    assert_eq!(Snippet::deserialize(&mut de).unwrap(), snippet);
+
+    let plain = "<Snippet><title>if</title><code>if a &lt; b {}</code></Snippet>";
+    let mut de = Deserializer::new_from_reader(plain.as_bytes()).distinguish_cdata(true);
+    assert!(Snippet::deserialize(&mut de).is_err());
+    assert_eq!(from_str::<Snippet>(plain).unwrap(), snippet);
+}
+
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Measurement {
+    #[serde(rename(serialize = "@scale", deserialize = "scale"))]
+    scale: f32,
+    value: f64,
+}
+
+#[test]
+fn tuple_struct_as_positional_attributes() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Color(u8, u8, u8);
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Pixel {
+        color: Color,
+        label: String,
+    }
+
+    let pixel = Pixel {
+        color: Color(255, 0, 0),
+        label: "red".to_string(),
+    };
+
+    let mut buffer = Vec::new();
+    {
+        let mut ser = Serializer::new(&mut buffer).positional_attributes(true);
+        pixel.serialize(&mut ser).unwrap();
+    }
+    let serialized_pixel = String::from_utf8(buffer).unwrap();
+    assert_eq!(
+        serialized_pixel,
+        r#"<Pixel><color v0="255" v1="0" v2="0"/><label>red</label></Pixel>"#
+    );
+
+    let deserialized_pixel: Pixel = from_str(&serialized_pixel).unwrap();
+    assert_eq!(deserialized_pixel, pixel);
+
+    assert!(to_string(&pixel).is_err());
+}
+
+
+#[test]
+fn attribute_prefix() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    #[serde(rename = "entry")]
+    struct Entry {
+        #[serde(rename = "@name")]
+        attribute: String,
+        name: String,
+    }
+
+    let entry = Entry {
+        attribute: "short".to_string(),
+        name: "long".to_string(),
+    };
+
+    let serialized_entry = to_string(&entry).unwrap();
+    assert_eq!(serialized_entry, r#"<entry name="short"><name>long</name></entry>"#);
+
+    let mut de = Deserializer::new_from_reader(serialized_entry.as_bytes()).attribute_prefix("@");
+    let deserialized_entry = Entry::deserialize(&mut de).unwrap();
+    assert_eq!(deserialized_entry, entry);
+
+    assert!(from_str::<Entry>(&serialized_entry).is_err());
+}
+
+
+#[test]
+fn special_floats() {
+    for &value in &[0.1, -1.5e-300, f64::INFINITY, f64::NEG_INFINITY] {
+        let measurement = Measurement {
+            scale: value as f32,
+            value,
+        };
+        let serialized = to_string(&measurement).unwrap();
+        let deserialized: Measurement = from_str(&serialized).unwrap();
+        assert_eq!(deserialized, measurement);
+    }
+
+    let serialized = to_string(&Measurement {
+        scale: f32::NAN,
+        value: f64::NAN,
+    }).unwrap();
+    assert_eq!(serialized, r#"<Measurement scale="NaN"><value>NaN</value></Measurement>"#);
+    let deserialized: Measurement = from_str(&serialized).unwrap();
+    assert!(deserialized.scale.is_nan() && deserialized.value.is_nan());
+}
+
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Envelope {
+    id: u32,
+    body: RawXml,
+}
+
+#[test]
+fn raw_xml_field() {
+    let body = concat!(
+        r#"<p class="x &amp; y">Fish&amp;Chips<b>bold</b></p><br/>"#,
+        r#"<ns:a xmlns:ns="urn:a"><ns:b ns:c="d"/></ns:a>"#
+    );
+    let src = format!("<Envelope><id>1</id><body>{}</body></Envelope>", body);
+
+    let envelope: Envelope = from_str(&src).unwrap();
+    assert_eq!(envelope.body, RawXml(body.to_string()));
+
+    let serialized_envelope = to_string(&envelope).unwrap();
+    assert_eq!(serialized_envelope, src);
+
+    let deserialized_envelope: Envelope = from_str(&serialized_envelope).unwrap();
+    assert_eq!(deserialized_envelope, envelope);
+}
+
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Rgb(u8, u8, u8);
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+impl FromStr for Rgb {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6)
+            .ok_or_else(|| format!("`{}` is not a #rrggbb color", s))?;
+        let channel = |i: usize| {
+            u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| e.to_string())
+        };
+        Ok(Rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Theme {
+    #[serde(with = "serde_xml_rs::display")]
+    background: Rgb,
+}
+
+#[test]
+fn display_helper() {
+    let theme = Theme {
+        background: Rgb(0x12, 0xab, 0xff),
+    };
+
+    let serialized_theme = to_string(&theme).unwrap();
+    assert_eq!(serialized_theme, "<Theme><background>#12abff</background></Theme>");
+
+    let deserialized_theme: Theme = from_str(&serialized_theme).unwrap();
+    assert_eq!(deserialized_theme, theme);
+
+    assert!(from_str::<Theme>("<Theme><background>blue</background></Theme>").is_err());
+}
+
+
+#[cfg(feature = "chrono")]
+#[test]
+fn chrono_datetimes() {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Event {
+        created: DateTime<Utc>,
+        #[serde(with = "serde_xml_rs::datetime")]
+        updated: DateTime<Utc>,
+    }
+
+    let event = Event {
+        created: Utc.with_ymd_and_hms(2021, 12, 31, 23, 59, 59).unwrap(),
+        updated: Utc.with_ymd_and_hms(2022, 1, 1, 12, 0, 0).unwrap(),
+    };
+
+    let serialized_event = to_string(&event).unwrap();
+    assert_eq!(
+        serialized_event,
+        "<Event><created>2021-12-31T23:59:59Z</created><updated>2022-01-01T12:00:00Z</updated></Event>"
+    );
+
+    let deserialized_event: Event = from_str(&serialized_event).unwrap();
+    assert_eq!(deserialized_event, event);
+
+    let offset: Event = from_str(
+        "<Event><created>2021-12-31T23:59:59Z</created><updated>2022-01-01T14:00:00+02:00</updated></Event>",
+    ).unwrap();
+    assert_eq!(offset, event);
+}
+
+
+#[cfg(feature = "chrono")]
+#[test]
+fn xsd_dates() {
+    use chrono::NaiveDate;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Holiday {
+        #[serde(with = "serde_xml_rs::xsd_date")]
+        date: NaiveDate,
+    }
+
+    let holiday = Holiday {
+        date: NaiveDate::from_ymd_opt(2021, 12, 31).unwrap(),
+    };
+
+    let serialized_holiday = to_string(&holiday).unwrap();
+    assert_eq!(serialized_holiday, "<Holiday><date>2021-12-31</date></Holiday>");
+
+    let deserialized_holiday: Holiday = from_str(&serialized_holiday).unwrap();
+    assert_eq!(deserialized_holiday, holiday);
+
+    for malformed in &["2021-13-01", "2021-12-31T00:00:00Z", "31/12/2021"] {
+        let src = format!("<Holiday><date>{}</date></Holiday>", malformed);
+        let err = from_str::<Holiday>(&src).unwrap_err();
+        assert!(err.to_string().contains("is not an xsd:date"), "{}", err);
+    }
+}
+
+
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn decimals() {
+    use std::str::FromStr;
+    use rust_decimal::Decimal;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Price {
+        #[serde(with = "serde_xml_rs::decimal")]
+        amount: Decimal,
+        #[serde(with = "serde_xml_rs::decimal")]
+        rate: Decimal,
+    }
+
+    let price = Price {
+        amount: Decimal::from_str("19.99").unwrap(),
+        rate: Decimal::from_str("0.1234567890123456789012345678").unwrap(),
+    };
+
+    let serialized_price = to_string(&price).unwrap();
+    assert_eq!(
+        serialized_price,
+        "<Price><amount>19.99</amount><rate>0.1234567890123456789012345678</rate></Price>"
+    );
+
+    let deserialized_price: Price = from_str(&serialized_price).unwrap();
+    assert_eq!(deserialized_price, price);
+    assert_eq!(deserialized_price.rate.to_string(), "0.1234567890123456789012345678");
+}
+
+
+#[test]
 fn round_trip_list_of_enums() {
     // Construct some inputs
     let nodes = Nodes {
@@ -57,17 +671,13 @@ fn round_trip_list_of_enums() {
         ],
     };
 
-    let should_be = r#"
-    <Nodes>
-        <Boolean>
-            true
-        </Boolean>
-        <Identifier>
-            <value>foo</value>
-            <index>5</index>
-        </Identifier>
-        <EOF />
-    </Nodes>"#;
+    let should_be = concat!(
+        "<Nodes>",
+        "<Boolean>true</Boolean>",
+        "<Identifier><value>foo</value><index>5</index></Identifier>",
+        "<EOF/>",
+        "</Nodes>"
+    );
 
     let serialized_nodes = to_string(&nodes).unwrap();
     assert_eq!(serialized_nodes, should_be);
@@ -77,3 +687,134 @@ fn round_trip_list_of_enums() {
     let deserialized_nodes: Nodes = from_str(serialized_nodes.as_str()).unwrap();
     assert_eq!(deserialized_nodes, nodes);
 }
+
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "t", content = "c")]
+enum Shape {
+    Circle { radius: u32 },
+    Rectangle { width: u32, height: u32 },
+}
+
+#[test]
+fn adjacently_tagged_enum() {
+    let shape = Shape::Rectangle {
+        width: 3,
+        height: 4,
+    };
+    let should_be = "<Shape><t>Rectangle</t><c><width>3</width><height>4</height></c></Shape>";
+
+    let mut buffer = Vec::new();
+    {
+        let mut ser = Serializer::new(&mut buffer).unit_variants_as_text(true);
+        shape.serialize(&mut ser).unwrap();
+    }
+    let serialized = String::from_utf8(buffer).unwrap();
+    assert_eq!(serialized, should_be);
+
+    let deserialized: Shape = from_str(&serialized).unwrap();
+    assert_eq!(deserialized, shape);
+
+    // By default the tag is written as an element of its own, which reads back too.
+    let serialized = to_string(&shape).unwrap();
+    assert_eq!(
+        serialized,
+        "<Shape><t><Rectangle/></t><c><width>3</width><height>4</height></c></Shape>"
+    );
+
+    let deserialized: Shape = from_str(&serialized).unwrap();
+    assert_eq!(deserialized, shape);
+}
+
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+enum Color {
+    Red,
+    Green,
+}
+
+#[test]
+fn unit_variants_as_text() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Shirt {
+        color: Color,
+        accents: Vec<Color>,
+        spare: Option<Color>,
+        by_size: BTreeMap<String, Color>,
+    }
+
+    let mut by_size = BTreeMap::new();
+    by_size.insert("small".to_string(), Color::Green);
+    let shirt = Shirt {
+        color: Color::Red,
+        accents: vec![Color::Green, Color::Red],
+        spare: Some(Color::Green),
+        by_size,
+    };
+    let should_be = concat!(
+        "<Shirt><color>Red</color><accents>Green</accents><accents>Red</accents>",
+        "<spare>Green</spare><by_size><small>Green</small></by_size></Shirt>"
+    );
+
+    let mut buffer = Vec::new();
+    {
+        let mut ser = Serializer::new(&mut buffer).unit_variants_as_text(true);
+        shirt.serialize(&mut ser).unwrap();
+    }
+    let serialized = String::from_utf8(buffer).unwrap();
+    assert_eq!(serialized, should_be);
+
+    let deserialized: Shirt = from_str(&serialized).unwrap();
+    assert_eq!(deserialized, shirt);
+
+    // By default each variant is an element inside the field's element.
+    let serialized = to_string(&shirt).unwrap();
+    assert_eq!(
+        serialized,
+        concat!(
+            "<Shirt><color><Red/></color><accents><Green/></accents><accents><Red/></accents>",
+            "<spare><Green/></spare><by_size><small><Green/></small></by_size></Shirt>"
+        )
+    );
+
+    let deserialized: Shirt = from_str(&serialized).unwrap();
+    assert_eq!(deserialized, shirt);
+}
+
+
+#[test]
+fn base64_bytes() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Attachment {
+        #[serde(rename = "@digest", with = "serde_xml_rs::base64")]
+        digest: Vec<u8>,
+        #[serde(with = "serde_xml_rs::base64")]
+        data: Vec<u8>,
+    }
+
+    let attachment = Attachment {
+        digest: vec![0xFB, 0xFF],
+        data: b"Man".to_vec(),
+    };
+    let should_be = r#"<Attachment digest="+/8="><data>TWFu</data></Attachment>"#;
+
+    let read = |s: &str| {
+        Attachment::deserialize(&mut Deserializer::new_from_reader(s.as_bytes()).attribute_prefix("@"))
+    };
+
+    let serialized = to_string(&attachment).unwrap();
+    assert_eq!(serialized, should_be);
+
+    let deserialized = read(&serialized).unwrap();
+    assert_eq!(deserialized, attachment);
+
+    for &(bytes, text) in &[(&b""[..], ""), (b"M", "TQ=="), (b"Ma", "TWE="), (b"Many", "TWFueQ==")] {
+        assert_eq!(serde_xml_rs::base64::encode(bytes), text);
+        assert_eq!(serde_xml_rs::base64::decode(text).unwrap(), bytes);
+    }
+
+    let wrapped = "<Attachment digest=\"+/8=\"><data>\n  TWFu\n  TQ==\n</data></Attachment>";
+    assert_eq!(read(wrapped).unwrap().data, b"ManM");
+    assert!(read(r#"<Attachment digest="+/8"><data>TWFu</data></Attachment>"#).is_err());
+    assert!(read(r#"<Attachment digest="+/8="><data>TQ==TWFu</data></Attachment>"#).is_err());
+}
@@ -1,10 +1,22 @@
 #[macro_use]
 extern crate serde_derive;
+extern crate serde;
 extern crate serde_xml_rs;
+extern crate url;
 
 extern crate log;
 
-use serde_xml_rs::from_str;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::rc::Rc;
+
+use serde::Deserialize;
+use serde_xml_rs::{
+    deserialize_if_complete, deserialize_iter, from_reader, from_str, Completion, Deserializer, Error, ErrorKind,
+    EventReader, KeySource, ParserConfig,
+};
 
 fn init_logger() {
     use log::{LogLevel, LogMetadata, LogRecord};
@@ -160,7 +172,7 @@ fn collection_of_enums() {
     let s = r##"
         <enums>
             <A>test</A>
-            <B name="hello" flag="t" />
+            <B name="hello" flag="true" />
             <C />
         </enums>
     "##;
@@ -181,3 +193,1563 @@ fn collection_of_enums() {
         }
     );
 }
+
+#[test]
+fn custom_entities() {
+    init_logger();
+
+    let s = r##"
+        <item name="hello">
+            <source>&copy; world.rs</source>
+        </item>
+    "##;
+
+    let mut entities = HashMap::new();
+    entities.insert("copy".to_string(), "\u{a9}".to_string());
+
+    let mut de = Deserializer::new_from_reader_with_entities(s.as_bytes(), entities);
+    let item = Item::deserialize(&mut de).unwrap();
+
+    assert_eq!(
+        item,
+        Item {
+            name: "hello".to_string(),
+            source: "\u{a9} world.rs".to_string(),
+        }
+    );
+
+    assert!(from_str::<Item>(s).is_err());
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Flag {
+    flag: bool,
+}
+
+#[test]
+fn capitalized_bools() {
+    init_logger();
+
+    let s = r##"<config><flag>True</flag></config>"##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).capitalized_bools(true);
+    assert_eq!(Flag::deserialize(&mut de).unwrap(), Flag { flag: true });
+
+    let s = r##"<config><flag>False</flag></config>"##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).capitalized_bools(true);
+    assert_eq!(Flag::deserialize(&mut de).unwrap(), Flag { flag: false });
+
+    assert!(from_str::<Flag>(s).is_err());
+
+    let s = r#"<config flag="True"/>"#;
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).capitalized_bools(true);
+    assert_eq!(Flag::deserialize(&mut de).unwrap(), Flag { flag: true });
+}
+
+#[test]
+fn lenient_bools() {
+    init_logger();
+
+    let inputs = vec![("1", true), ("yes", true), ("0", false), ("no", false)];
+    for (text, should_be) in inputs {
+        let s = format!("<config><flag>{}</flag></config>", text);
+        assert_eq!(from_str::<Flag>(&s).unwrap(), Flag { flag: should_be });
+    }
+
+    // Attribute values are read the same way as element text.
+    let inputs = vec![("true", true), ("1", true), ("false", false), ("0", false)];
+    for (text, should_be) in inputs {
+        let s = format!(r#"<config flag="{}"/>"#, text);
+        assert_eq!(from_str::<Flag>(&s).unwrap(), Flag { flag: should_be });
+    }
+    assert!(from_str::<Flag>(r#"<config flag="2"/>"#).is_err());
+
+    let mut de = Deserializer::new_from_reader("<config><flag>2</flag></config>".as_bytes());
+    match *Flag::deserialize(&mut de).unwrap_err().kind() {
+        ErrorKind::Custom(ref message) => assert_eq!(
+            message,
+            "`2` is not a boolean, expected true, false, 1, 0, yes or no"
+        ),
+        ref other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[test]
+fn xsd_bools() {
+    init_logger();
+
+    let inputs = vec![("true", true), ("1", true), ("false", false), ("0", false)];
+    for (text, should_be) in inputs {
+        let s = format!("<config><flag>{}</flag></config>", text);
+        let mut de = Deserializer::new_from_reader(s.as_bytes()).xsd_bools(true);
+        assert_eq!(Flag::deserialize(&mut de).unwrap(), Flag { flag: should_be });
+    }
+
+    for text in &["yes", "True", "on"] {
+        let s = format!("<config><flag>{}</flag></config>", text);
+        let mut de = Deserializer::new_from_reader(s.as_bytes())
+            .capitalized_bools(true)
+            .xsd_bools(true);
+        assert!(Flag::deserialize(&mut de).is_err());
+    }
+
+    let s = r#"<config flag="0"/>"#;
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).xsd_bools(true);
+    assert_eq!(Flag::deserialize(&mut de).unwrap(), Flag { flag: false });
+
+    let s = r#"<config flag="yes"/>"#;
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).xsd_bools(true);
+    assert!(Flag::deserialize(&mut de).is_err());
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Config {
+    name: String,
+    retries: u32,
+}
+
+#[test]
+fn deserialize_all() {
+    init_logger();
+
+    let s = r##"
+        <order>
+            <item><name>Apple</name><source>Tree</source></item>
+            <note>fragile</note>
+            <item><name>Banana</name><source>Store</source></item>
+            <note>keep cool</note>
+            <box><item><name>Nested</name><source>Ignored</source></item></box>
+            <item><name>Cherry</name><source>Tree</source></item>
+        </order>
+    "##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes());
+    let items: Vec<Item> = de.deserialize_all("item").unwrap();
+    let names: Vec<&str> = items.iter().map(|item| item.name.as_str()).collect();
+    assert_eq!(names, vec!["Apple", "Banana", "Cherry"]);
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes());
+    let notes: Vec<String> = de.deserialize_all("note").unwrap();
+    assert_eq!(notes, vec!["fragile", "keep cool"]);
+}
+
+#[test]
+fn deserialize_named_element() {
+    init_logger();
+
+    let s = r##"
+        <document>
+            <header><name>ignored</name></header>
+            <config name="main">
+                <retries>3</retries>
+            </config>
+            <footer>done</footer>
+        </document>
+    "##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes());
+    let config: Config = de.deserialize_element("config").unwrap();
+
+    assert_eq!(
+        config,
+        Config {
+            name: "main".to_string(),
+            retries: 3,
+        }
+    );
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes());
+    assert!(de.deserialize_element::<Config>("missing").is_err());
+}
+
+#[derive(Debug, PartialEq)]
+struct Bytes(Vec<u8>);
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for BytesVisitor {
+            type Value = Bytes;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("bytes")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Bytes, E> {
+                Ok(Bytes(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Bytes, E> {
+                Ok(Bytes(v))
+            }
+        }
+
+        deserializer.deserialize_bytes(BytesVisitor)
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Blob {
+    data: Bytes,
+    empty: Bytes,
+}
+
+#[test]
+fn text_as_bytes() {
+    init_logger();
+
+    let s = r##"<blob><data>aGVsbG8=</data><empty></empty></blob>"##;
+
+    let blob: Blob = from_str(s).unwrap();
+
+    assert_eq!(
+        blob,
+        Blob {
+            data: Bytes(b"aGVsbG8=".to_vec()),
+            empty: Bytes(Vec::new()),
+        }
+    );
+}
+
+#[test]
+fn ignore_namespaces() {
+    init_logger();
+
+    let s = r##"
+        <a:item xmlns:a="urn:a" xmlns:b="urn:b" xmlns="urn:default" b:name="hello">
+            <b:source>world.rs</b:source>
+        </a:item>
+    "##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).ignore_namespaces(true);
+    let item = Item::deserialize(&mut de).unwrap();
+
+    assert_eq!(
+        item,
+        Item {
+            name: "hello".to_string(),
+            source: "world.rs".to_string(),
+        }
+    );
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Pairs {
+    kv: (String, String),
+    triple: (String, u32, String),
+}
+
+#[test]
+fn tuple_from_attributes_and_text() {
+    init_logger();
+
+    let s = r##"
+        <pairs>
+            <kv k="a">b</kv>
+            <triple k="x" n="2">y</triple>
+        </pairs>
+    "##;
+
+    let pairs: Pairs = from_str(s).unwrap();
+
+    assert_eq!(
+        pairs,
+        Pairs {
+            kv: ("a".to_string(), "b".to_string()),
+            triple: ("x".to_string(), 2, "y".to_string()),
+        }
+    );
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Host {
+    addr: IpAddr,
+    #[serde(rename = "fallback")]
+    fallbacks: Vec<IpAddr>,
+}
+
+#[test]
+fn from_str_types() {
+    init_logger();
+
+    let s = r##"
+        <host>
+            <addr>127.0.0.1</addr>
+            <fallback>::1</fallback>
+            <fallback>10.0.0.1</fallback>
+        </host>
+    "##;
+
+    let host: Host = from_str(s).unwrap();
+
+    assert_eq!(
+        host,
+        Host {
+            addr: "127.0.0.1".parse().unwrap(),
+            fallbacks: vec!["::1".parse().unwrap(), "10.0.0.1".parse().unwrap()],
+        }
+    );
+    assert!(from_str::<Host>("<host><addr>nope</addr></host>").is_err());
+}
+
+#[test]
+fn url_from_text() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Link {
+        href: url::Url,
+    }
+
+    let s = r##"<link><href>https://example.com/a?b=c&amp;d=e</href></link>"##;
+
+    let link: Link = from_str(s).unwrap();
+
+    assert_eq!(
+        link,
+        Link {
+            href: url::Url::parse("https://example.com/a?b=c&d=e").unwrap(),
+        }
+    );
+}
+
+#[test]
+fn utf16_with_bom() {
+    init_logger();
+
+    let s = "<?xml version=\"1.0\" encoding=\"UTF-16\"?><item name=\"h\u{e9}llo\" source=\"w\u{f6}rld.rs\" />";
+
+    let should_be = Item {
+        name: "h\u{e9}llo".to_string(),
+        source: "w\u{f6}rld.rs".to_string(),
+    };
+
+    let mut le = vec![0xFF, 0xFE];
+    let mut be = vec![0xFE, 0xFF];
+    for unit in s.encode_utf16() {
+        le.extend_from_slice(&[unit as u8, (unit >> 8) as u8]);
+        be.extend_from_slice(&[(unit >> 8) as u8, unit as u8]);
+    }
+
+    assert_eq!(from_reader::<_, Item>(&le[..]).unwrap(), should_be);
+    assert_eq!(from_reader::<_, Item>(&be[..]).unwrap(), should_be);
+}
+
+#[test]
+fn latin1_declaration() {
+    init_logger();
+
+    let mut s = br##"<?xml version="1.0" encoding="ISO-8859-1"?><item name="h"##.to_vec();
+    s.push(0xE9);
+    s.extend_from_slice(br##"llo" source="world.rs" />"##);
+
+    let item: Item = from_reader(&s[..]).unwrap();
+
+    assert_eq!(
+        item,
+        Item {
+            name: "h\u{e9}llo".to_string(),
+            source: "world.rs".to_string(),
+        }
+    );
+}
+
+#[test]
+fn name_map() {
+    init_logger();
+
+    let s = r##"
+        <legacy_item title="hello">
+            <origin>world.rs</origin>
+        </legacy_item>
+    "##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).with_name_map(|name| match name {
+        "title" => Cow::Borrowed("name"),
+        "origin" => Cow::Owned("source".to_string()),
+        other => Cow::Borrowed(other),
+    });
+    let item = Item::deserialize(&mut de).unwrap();
+
+    assert_eq!(
+        item,
+        Item {
+            name: "hello".to_string(),
+            source: "world.rs".to_string(),
+        }
+    );
+}
+
+#[test]
+fn strip_text_bom() {
+    init_logger();
+
+    let s = "<item name=\"hello\"><source>\u{feff}world.rs</source></item>";
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).strip_text_bom(true);
+    let item = Item::deserialize(&mut de).unwrap();
+
+    assert_eq!(
+        item,
+        Item {
+            name: "hello".to_string(),
+            source: "world.rs".to_string(),
+        }
+    );
+
+    let item: Item = from_str(s).unwrap();
+    assert_eq!(item.source, "\u{feff}world.rs");
+}
+
+#[test]
+fn either_source_fields() {
+    init_logger();
+
+    let inputs = vec![
+        r##"<item name="hello" source="world.rs" />"##,
+        r##"<item name="hello"><source>world.rs</source></item>"##,
+        r##"<item name="hello" source="world.rs"><source>ignored.rs</source></item>"##,
+    ];
+
+    assert!(from_str::<Item>(inputs[2]).is_err());
+
+    for s in inputs {
+        let mut de = Deserializer::new_from_reader(s.as_bytes()).either_source(&["source"]);
+        let item = Item::deserialize(&mut de).unwrap();
+
+        assert_eq!(
+            item,
+            Item {
+                name: "hello".to_string(),
+                source: "world.rs".to_string(),
+            }
+        );
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Paragraph {
+    class: String,
+    #[serde(rename = "$value", with = "serde_xml_rs::inner_text")]
+    text: String,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Article {
+    #[serde(with = "serde_xml_rs::inner_text")]
+    title: String,
+    #[serde(rename = "p")]
+    paragraph: Paragraph,
+}
+
+#[test]
+fn inner_text() {
+    init_logger();
+
+    let s = r##"
+        <article>
+            <title>Plain</title>
+            <p class="intro">Hello <b>big <i>wide</i></b> world</p>
+        </article>
+    "##;
+
+    let article: Article = from_str(s).unwrap();
+
+    assert_eq!(
+        article,
+        Article {
+            title: "Plain".to_string(),
+            paragraph: Paragraph {
+                class: "intro".to_string(),
+                text: "Hello big wide world".to_string(),
+            },
+        }
+    );
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Namespaced {
+    #[serde(rename = "$namespace")]
+    namespace: Option<String>,
+    name: String,
+}
+
+#[test]
+fn capture_namespace() {
+    init_logger();
+
+    let s = r##"<x:item xmlns:x="urn:example:items" name="hello" />"##;
+    let item: Namespaced = from_str(s).unwrap();
+
+    assert_eq!(
+        item,
+        Namespaced {
+            namespace: Some("urn:example:items".to_string()),
+            name: "hello".to_string(),
+        }
+    );
+
+    let s = r##"<item name="hello" />"##;
+    let item: Namespaced = from_str(s).unwrap();
+
+    assert_eq!(
+        item,
+        Namespaced {
+            namespace: None,
+            name: "hello".to_string(),
+        }
+    );
+}
+
+#[test]
+fn max_text_bytes() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Lines {
+        line: Vec<String>,
+    }
+
+    let s = r##"<lines><line>0123456789</line><line>0123456789</line></lines>"##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes());
+    de.set_max_text_bytes(20);
+    assert_eq!(Lines::deserialize(&mut de).unwrap().line.len(), 2);
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes());
+    de.set_max_text_bytes(15);
+    let err = Lines::deserialize(&mut de).unwrap_err();
+    assert!(err.to_string().contains("exceeds the limit of 15 bytes"));
+}
+
+#[test]
+fn max_attributes() {
+    init_logger();
+
+    let s = r##"<item name="Apple" source="Tree"/>"##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes());
+    de.set_max_attributes(2);
+    assert_eq!(Item::deserialize(&mut de).unwrap().name, "Apple");
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes());
+    de.set_max_attributes(1);
+    let err = Item::deserialize(&mut de).unwrap_err();
+    assert!(err.to_string().contains("has 2 attributes, more than the limit of 1"));
+}
+
+#[test]
+fn runtime_aliases() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Order {
+        qty: u32,
+    }
+
+    let documents = vec![
+        r##"<order><qty>3</qty></order>"##,
+        r##"<order><quantity>3</quantity></order>"##,
+        r##"<order quantity="3" />"##,
+    ];
+
+    for s in documents {
+        let mut de = Deserializer::new_from_reader(s.as_bytes());
+        de.add_alias("quantity", "qty");
+        assert_eq!(Order::deserialize(&mut de).unwrap(), Order { qty: 3 });
+    }
+}
+
+#[test]
+fn text_split_across_nodes() {
+    init_logger();
+
+    let s = r##"
+        <item name="hello">
+            <source>world<!-- generated --><?tool note?>.rs</source>
+        </item>
+    "##;
+
+    let item: Item = from_str(s).unwrap();
+
+    assert_eq!(
+        item,
+        Item {
+            name: "hello".to_string(),
+            source: "world.rs".to_string(),
+        }
+    );
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Count {
+        count: u32,
+    }
+
+    let count: Count = from_str("<c><count>1<?pi?>2</count></c>").unwrap();
+    assert_eq!(count, Count { count: 12 });
+
+    // Whitespace at the split is kept as a single space, however much was trimmed.
+    let s = "<item><name>hello <?tool note?>  world</name><source>a<?pi?> b</source></item>";
+    let item: Item = from_str(s).unwrap();
+    assert_eq!(
+        item,
+        Item {
+            name: "hello world".to_string(),
+            source: "a b".to_string(),
+        }
+    );
+}
+
+#[test]
+fn expose_attributes() {
+    init_logger();
+
+    let s = r##"
+        <links>
+            <link href="https://example.com" rel="next" data-id="7">Example</link>
+        </links>
+    "##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).expose_attributes(true);
+    let text: String = de.deserialize_element("link").unwrap();
+    assert_eq!(text, "Example");
+
+    let attributes: Vec<(&str, &str)> = de.current_attributes()
+        .iter()
+        .map(|attr| (attr.name.local_name.as_str(), attr.value.as_str()))
+        .collect();
+    assert_eq!(
+        attributes,
+        vec![("href", "https://example.com"), ("rel", "next"), ("data-id", "7")]
+    );
+
+    // Between the elements of a document read one by one, they are those of the
+    // element read last.
+    let s = r##"<links><link rel="prev">Back</link><link rel="next">On</link></links>"##;
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).expose_attributes(true);
+    for &(text, rel) in &[("Back", "prev"), ("On", "next")] {
+        assert_eq!(de.deserialize_element::<String>("link").unwrap(), text);
+        assert_eq!(de.current_attributes()[0].value, rel);
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Tagged {
+    tags: Vec<String>,
+    ids: Vec<u32>,
+}
+
+#[test]
+fn singular_attribute_seqs() {
+    init_logger();
+
+    let s = r##"<item tags="a" ids="7"/>"##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).singular_attribute_seqs(true);
+    let item = Tagged::deserialize(&mut de).unwrap();
+    assert_eq!(
+        item,
+        Tagged {
+            tags: vec!["a".to_string()],
+            ids: vec![7],
+        }
+    );
+
+    assert!(from_str::<Tagged>(s).is_err());
+}
+
+#[test]
+fn current_base() {
+    init_logger();
+
+    let s = r##"
+        <site xml:base="https://example.com/docs/">
+            <section xml:base="guide/">
+                <page>intro.html</page>
+                <page xml:base="/api/">index.html</page>
+                <page>setup.html</page>
+            </section>
+            <page>about.html</page>
+        </site>
+    "##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes());
+    assert_eq!(de.current_base(), None);
+
+    let mut pages = Vec::new();
+    for _ in 0..4 {
+        let page: String = de.deserialize_element("page").unwrap();
+        pages.push(page);
+    }
+    assert_eq!(de.current_base(), Some("https://example.com/docs/"));
+    assert_eq!(pages, vec!["intro.html", "index.html", "setup.html", "about.html"]);
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes());
+    let _: String = de.deserialize_element("page").unwrap();
+    assert_eq!(de.current_base(), Some("https://example.com/docs/guide/"));
+    let _: String = de.deserialize_element("page").unwrap();
+    assert_eq!(de.current_base(), Some("https://example.com/docs/guide/"));
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Figure {
+    Circle { r: u32 },
+    Square { s: u32 },
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Drawing {
+    #[serde(rename = "$value")]
+    figures: Vec<Figure>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Framed {
+    figure: Figure,
+}
+
+#[test]
+fn enum_variant_with_attributes() {
+    init_logger();
+
+    let figure: Figure = from_str(r#"<circle r="5"/>"#).unwrap();
+    assert_eq!(figure, Figure::Circle { r: 5 });
+
+    let drawing: Drawing = from_str(r#"<drawing><circle r="5"/><square s="2"></square></drawing>"#)
+        .unwrap();
+    assert_eq!(
+        drawing,
+        Drawing {
+            figures: vec![Figure::Circle { r: 5 }, Figure::Square { s: 2 }],
+        }
+    );
+    let framed: Framed = from_str(r#"<framed><figure><square s="3"/></figure></framed>"#).unwrap();
+    assert_eq!(
+        framed,
+        Framed {
+            figure: Figure::Square { s: 3 },
+        }
+    );
+}
+
+#[test]
+fn text_before_root() {
+    init_logger();
+
+    for s in &["garbage<root/>", "  garbage\n<root>text</root>"] {
+        assert!(matches!(
+            from_str::<String>(s),
+            Err(Error(ErrorKind::Syntax(_), _))
+        ));
+    }
+
+    let text: String = from_str("  \n<root>text</root>").unwrap();
+    assert_eq!(text, "text");
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Sorted {
+    #[serde(rename = "$pi")]
+    processing_instructions: Vec<(String, String)>,
+    entry: Vec<Entry>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Entry {
+    #[serde(rename = "$pi", default)]
+    processing_instructions: Vec<(String, String)>,
+    name: String,
+}
+
+#[test]
+fn capture_processing_instructions() {
+    init_logger();
+
+    let s = r##"
+        <list>
+            <?sort asc?>
+            <entry><name>a</name></entry>
+            <entry><?hidden?><name>b</name></entry>
+        </list>
+    "##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).capture_processing_instructions(true);
+    let list = Sorted::deserialize(&mut de).unwrap();
+    assert_eq!(
+        list,
+        Sorted {
+            processing_instructions: vec![("sort".to_string(), "asc".to_string())],
+            entry: vec![
+                Entry {
+                    processing_instructions: vec![],
+                    name: "a".to_string(),
+                },
+                Entry {
+                    processing_instructions: vec![("hidden".to_string(), "".to_string())],
+                    name: "b".to_string(),
+                },
+            ],
+        }
+    );
+
+    let entry: Entry = from_str("<entry><?hidden?><name>b</name></entry>").unwrap();
+    assert!(entry.processing_instructions.is_empty());
+}
+
+#[derive(Debug, Deserialize, PartialEq, Default)]
+struct Limits {
+    min: u32,
+    max: u32,
+}
+
+fn default_port() -> u16 {
+    8080
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Server {
+    #[serde(default)]
+    name: String,
+    #[serde(default = "default_port")]
+    port: u16,
+    #[serde(default)]
+    limits: Limits,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[test]
+fn empty_as_default() {
+    init_logger();
+
+    let s = r##"
+        <server>
+            <name></name>
+            <port/>
+            <limits/>
+            <tags/>
+        </server>
+    "##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).empty_as_default(true);
+    let server = Server::deserialize(&mut de).unwrap();
+    assert_eq!(
+        server,
+        Server {
+            name: "".to_string(),
+            port: 8080,
+            limits: Limits::default(),
+            tags: vec![],
+        }
+    );
+
+    let s = r##"<server><port>80</port><limits><min>1</min><max>2</max></limits><tags/></server>"##;
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).empty_as_default(true);
+    let server = Server::deserialize(&mut de).unwrap();
+    assert_eq!(server.port, 80);
+    assert_eq!(server.limits, Limits { min: 1, max: 2 });
+
+    assert!(from_str::<Server>("<server><port/></server>").is_err());
+}
+
+#[test]
+fn last_key_source() {
+    init_logger();
+
+    let inputs = vec![
+        (r#"<item name="Apple"><source>Tree</source></item>"#, KeySource::Element),
+        (r#"<item source="Tree"><name>Apple</name></item>"#, KeySource::Element),
+        (r#"<item name="Apple" source="Tree"/>"#, KeySource::Attribute),
+    ];
+
+    for (s, should_be) in inputs {
+        let mut de = Deserializer::new_from_reader(s.as_bytes());
+        assert_eq!(de.last_key_source(), None);
+        let item = Item::deserialize(&mut de).unwrap();
+        assert_eq!(item.name, "Apple");
+        assert_eq!(de.last_key_source(), Some(should_be));
+    }
+}
+
+#[test]
+fn strip_trailing_digits() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Contact {
+        name: String,
+        addr: Vec<String>,
+    }
+
+    let s = r##"
+        <contact>
+            <name>Ann</name>
+            <addr1>1 Main St</addr1>
+            <addr2>Springfield</addr2>
+        </contact>
+    "##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).strip_trailing_digits(true);
+    let contact = Contact::deserialize(&mut de).unwrap();
+    assert_eq!(
+        contact,
+        Contact {
+            name: "Ann".to_string(),
+            addr: vec!["1 Main St".to_string(), "Springfield".to_string()],
+        }
+    );
+
+    assert!(from_str::<Contact>(s).is_err());
+}
+
+#[test]
+fn deserialize_if_complete_in_chunks() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Project {
+        name: String,
+        libraries: Vec<String>,
+    }
+
+    let chunks: Vec<&[u8]> = vec![
+        b"<project><name>serde</name><libraries>xml</libr",
+        b"aries><libraries>json</libraries></project>",
+    ];
+
+    let mut buffer = Vec::new();
+    buffer.extend_from_slice(chunks[0]);
+    assert_eq!(deserialize_if_complete::<Project>(&buffer).unwrap(), Completion::NeedsMoreInput);
+
+    buffer.extend_from_slice(chunks[1]);
+    assert_eq!(
+        deserialize_if_complete::<Project>(&buffer).unwrap(),
+        Completion::Complete(Project {
+            name: "serde".to_string(),
+            libraries: vec!["xml".to_string(), "json".to_string()],
+        })
+    );
+
+    assert_eq!(deserialize_if_complete::<Project>(b"").unwrap(), Completion::NeedsMoreInput);
+    assert!(deserialize_if_complete::<Project>(b"<project></name>").is_err());
+}
+
+#[test]
+fn on_start_element() {
+    init_logger();
+
+    let s = r##"
+        <project name="serde">
+            <item name="xml" source="crates.io"/>
+            <item name="json" source="crates.io"/>
+        </project>
+    "##;
+
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let hook_seen = seen.clone();
+    let mut de = Deserializer::new_from_reader(s.as_bytes())
+        .on_start_element(move |name, attributes| {
+            hook_seen.borrow_mut().push((name.local_name.clone(), attributes.len()));
+        });
+    let project = Project::deserialize(&mut de).unwrap();
+    assert_eq!(project.items.len(), 2);
+
+    assert_eq!(
+        *seen.borrow(),
+        vec![
+            ("project".to_string(), 1),
+            ("item".to_string(), 2),
+            ("item".to_string(), 2),
+        ]
+    );
+}
+
+#[test]
+fn bom_separated_documents() {
+    init_logger();
+
+    let s = concat!(
+        "\u{feff}<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<item name=\"hello\"><source>world.rs</source></item>\n",
+        "\u{feff}<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<item name=\"goodbye\"><source>moon.rs</source></item>\n"
+    );
+
+    let items: Vec<Item> = deserialize_iter(s.as_bytes())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        items,
+        vec![
+            Item {
+                name: "hello".to_string(),
+                source: "world.rs".to_string(),
+            },
+            Item {
+                name: "goodbye".to_string(),
+                source: "moon.rs".to_string(),
+            },
+        ]
+    );
+
+    assert!(from_str::<Vec<Item>>(s).is_err());
+
+    let mut items = deserialize_iter::<_, Item>("\u{feff}<item/>\u{feff}<item>".as_bytes());
+    assert!(items.next().unwrap().is_err());
+    assert!(items.next().unwrap().is_err());
+    assert!(items.next().is_none());
+}
+
+#[test]
+fn allowed_roots() {
+    init_logger();
+
+    let roots = ["item", "project"];
+
+    let s = r##"<item name="hello" source="world.rs" />"##;
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).allowed_roots(&roots);
+    let item = Item::deserialize(&mut de).unwrap();
+    assert_eq!(item.name, "hello");
+
+    let s = r##"<thing name="hello" source="world.rs" />"##;
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).allowed_roots(&roots);
+    match *Item::deserialize(&mut de).unwrap_err().kind() {
+        ErrorKind::Custom(ref message) => assert_eq!(
+            message,
+            "Root element <thing> is not one of the allowed roots: item, project"
+        ),
+        ref other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[test]
+fn xsi_nil_as_none() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Person {
+        nickname: Option<String>,
+        name: String,
+    }
+
+    let s = r##"
+        <person xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+            <nickname xsi:nil="true"/>
+            <name>Ann</name>
+        </person>
+    "##;
+    assert_eq!(
+        from_str::<Person>(s).unwrap(),
+        Person {
+            nickname: None,
+            name: "Ann".to_string(),
+        }
+    );
+
+    let s = r##"
+        <person xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+            <nickname xsi:nil="false">Annie</nickname>
+            <name>Ann</name>
+        </person>
+    "##;
+    assert_eq!(from_str::<Person>(s).unwrap().nickname, Some("Annie".to_string()));
+}
+
+#[test]
+fn strip_underscores() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        id: u32,
+        internal: String,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+
+    let s = r##"
+        <record>
+            <id>1</id>
+            <_internal_>x</_internal_>
+            <__tags>a</__tags>
+            <__tags>b</__tags>
+        </record>
+    "##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).strip_underscores(true);
+    let record = Record::deserialize(&mut de).unwrap();
+    assert_eq!(
+        record,
+        Record {
+            id: 1,
+            internal: "x".to_string(),
+            tags: vec!["a".to_string(), "b".to_string()],
+        }
+    );
+
+    assert!(from_str::<Record>(s).is_err());
+}
+
+#[test]
+fn wide_integer_attributes() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Counter {
+        total: u128,
+        delta: i128,
+    }
+
+    let s = r##"<counter total="36893488147419103232" delta="-36893488147419103232"/>"##;
+    assert_eq!(
+        from_str::<Counter>(s).unwrap(),
+        Counter {
+            total: 1 << 65,
+            delta: -(1 << 65),
+        }
+    );
+}
+
+#[test]
+fn last_populated_fields() {
+    init_logger();
+
+    #[derive(Debug, Default, Deserialize, PartialEq)]
+    #[serde(default)]
+    struct Limits {
+        min: u32,
+        max: u32,
+    }
+
+    #[derive(Debug, Default, Deserialize, PartialEq)]
+    #[serde(default)]
+    struct Settings {
+        name: String,
+        retries: u32,
+        limits: Limits,
+        verbose: bool,
+    }
+
+    let s = r##"
+        <settings name="test">
+            <limits><max>5</max></limits>
+            <unknown>ignored</unknown>
+            <retries>3</retries>
+        </settings>
+    "##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).record_populated_fields(true);
+    let settings = Settings::deserialize(&mut de).unwrap();
+    assert_eq!(settings.limits, Limits { min: 0, max: 5 });
+    assert_eq!(de.last_populated_fields(), &["name", "limits", "retries"]);
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes());
+    Settings::deserialize(&mut de).unwrap();
+    assert!(de.last_populated_fields().is_empty());
+}
+
+#[test]
+fn error_positions() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Limits {
+        max: u32,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Settings {
+        name: String,
+        limits: Limits,
+    }
+
+    let s = "<settings>\n  <name>test</name>\n  <limits>\n    <max>lots</max>\n  </limits>\n</settings>";
+    let err = from_str::<Settings>(s).unwrap_err();
+    assert!(matches!(*err.kind(), ErrorKind::ParseIntError(_)), "{:?}", err);
+    let position = err.position().unwrap();
+    assert_eq!((position.row, position.column), (3, 13));
+    assert!(err.iter().any(|cause| cause.to_string() == "at line 4, column 14"));
+    assert_eq!(err.to_string(), "invalid digit found in string at line 4, column 14");
+
+    // The same goes for a `Deserializer` used directly, with any options.
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).strict(true);
+    let err = Settings::deserialize(&mut de).unwrap_err();
+    assert!(matches!(*err.kind(), ErrorKind::ParseIntError(_)), "{:?}", err);
+    assert_eq!(err.position(), Some(position));
+
+    let s = s.replace("lots", "5");
+    let settings = Settings { name: "test".to_string(), limits: Limits { max: 5 } };
+    assert_eq!(from_str::<Settings>(&s).unwrap(), settings);
+
+    let err = from_str::<Settings>("<settings>\n  <name>test</nam>\n</settings>").unwrap_err();
+    let position = err.position().unwrap();
+    assert_eq!((position.row, position.column), (1, 17));
+
+    let err = from_str::<Settings>("<settings>\n<name>test</name></settings>").unwrap_err();
+    assert!(matches!(*err.kind(), ErrorKind::Custom(_)), "{:?}", err);
+    assert_eq!(err.to_string(), "custom: 'missing field `limits`' at line 2, column 11");
+}
+
+#[test]
+fn strict_mode() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Limits {
+        max: u32,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Settings {
+        name: String,
+        limits: Limits,
+    }
+
+    let s = r##"<settings version="2"><name>test</name><limits><max>5</max></limits></settings>"##;
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).strict(true);
+    let settings = Settings { name: "test".to_string(), limits: Limits { max: 5 } };
+    assert_eq!(Settings::deserialize(&mut de).unwrap(), settings);
+
+    let s = r##"<settings><name>test</name><limits><max>5</max><mni>1</mni></limits></settings>"##;
+    assert_eq!(from_str::<Settings>(s).unwrap(), settings);
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).strict(true);
+    let err = Settings::deserialize(&mut de).unwrap_err();
+    assert!(err.to_string().contains("unknown field `mni`, expected `max`"), "{}", err);
+}
+
+#[test]
+fn coalesced_text() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Note {
+        #[serde(with = "serde_xml_rs::coalesced_text")]
+        body: String,
+        #[serde(with = "serde_xml_rs::inner_text")]
+        summary: String,
+    }
+
+    let s = r##"<note><body>one<!-- split -->two <![CDATA[<three>]]></body><summary>a<!-- split -->b</summary></note>"##;
+    let config = ParserConfig::new()
+        .coalesce_characters(false)
+        .ignore_comments(false)
+        .cdata_to_characters(false);
+    let mut de = Deserializer::new(EventReader::new_with_config(s.as_bytes(), config));
+
+    assert_eq!(
+        Note::deserialize(&mut de).unwrap(),
+        Note {
+            body: "onetwo <three>".to_string(),
+            summary: "a b".to_string(),
+        }
+    );
+}
+
+#[test]
+fn keep_whitespace() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Page {
+        title: String,
+        pre: String,
+    }
+
+    let s = "<page><title> Listing </title><pre>  hi  </pre></page>";
+    let page = Page {
+        title: "Listing".to_string(),
+        pre: "hi".to_string(),
+    };
+    assert_eq!(from_str::<Page>(s).unwrap(), page);
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).trim_whitespace(false);
+    let page = Page {
+        title: " Listing ".to_string(),
+        pre: "  hi  ".to_string(),
+    };
+    assert_eq!(Page::deserialize(&mut de).unwrap(), page);
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Listing {
+        line: Vec<String>,
+        title: String,
+    }
+
+    // The indentation between elements isn't text of the enclosing element.
+    let s = "<listing>\n  <line>  a</line>\n  <line>b  </line>\n  <title>x</title>\n</listing>";
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).trim_whitespace(false);
+    let listing = Listing {
+        line: vec!["  a".to_string(), "b  ".to_string()],
+        title: "x".to_string(),
+    };
+    assert_eq!(Listing::deserialize(&mut de).unwrap(), listing);
+}
+
+#[test]
+fn mixed_content_value() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Text {
+        class: String,
+        #[serde(rename = "$value")]
+        text: String,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Runs {
+        class: String,
+        #[serde(rename = "$value")]
+        runs: Vec<String>,
+    }
+
+    let s = r##"<p class="a">hello <b>big <i>wide</i></b> world</p>"##;
+    assert_eq!(
+        from_str::<Text>(s).unwrap(),
+        Text {
+            class: "a".to_string(),
+            text: "hello big wide world".to_string(),
+        }
+    );
+    let s = r##"<p class="a">hello <b>big</b> world</p>"##;
+    assert_eq!(
+        from_str::<Runs>(s).unwrap(),
+        Runs {
+            class: "a".to_string(),
+            runs: vec!["hello".to_string(), "big".to_string(), "world".to_string()],
+        }
+    );
+
+    let s = r##"<p class="a">hello</p>"##;
+    assert_eq!(from_str::<Text>(s).unwrap().text, "hello");
+}
+
+#[test]
+fn fragments() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Entry {
+        key: String,
+        value: u32,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Settings {
+        name: String,
+        entry: Vec<Entry>,
+    }
+
+    let s = r##"
+        <entry key="a"><value>1</value></entry>
+        <entry key="b"><value>2</value></entry>
+    "##;
+    let entries = vec![
+        Entry { key: "a".to_string(), value: 1 },
+        Entry { key: "b".to_string(), value: 2 },
+    ];
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).fragment(true);
+    assert_eq!(Vec::<Entry>::deserialize(&mut de).unwrap(), entries);
+
+    let s = r##"
+        <name>test</name>
+        <entry key="a"><value>1</value></entry>
+        <entry key="b"><value>2</value></entry>
+    "##;
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).fragment(true);
+    assert_eq!(
+        Settings::deserialize(&mut de).unwrap(),
+        Settings {
+            name: "test".to_string(),
+            entry: entries,
+        }
+    );
+    assert!(from_str::<Settings>(s).is_err());
+
+    let mut de = Deserializer::new_from_reader("<name>test</name> text".as_bytes()).fragment(true);
+    assert!(Settings::deserialize(&mut de).is_err());
+}
+
+#[test]
+fn ignore_case() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        id: u32,
+        kind: String,
+        tag: Vec<String>,
+    }
+
+    let s = r##"<record KIND="note"><ID>7</ID><Tag>a</Tag><TAG>b</TAG></record>"##;
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).ignore_case(true);
+    assert_eq!(
+        Record::deserialize(&mut de).unwrap(),
+        Record {
+            id: 7,
+            kind: "note".to_string(),
+            tag: vec!["a".to_string(), "b".to_string()],
+        }
+    );
+
+    assert!(from_str::<Record>(s).is_err());
+}
+
+#[test]
+fn check_root_name() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Point {
+        x: i32,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename = "pt")]
+    struct Renamed {
+        x: i32,
+    }
+
+    let s = "<Wrong><x>1</x></Wrong>";
+    assert_eq!(from_str::<Point>(s).unwrap(), Point { x: 1 });
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).check_root_name(true);
+    match *Point::deserialize(&mut de).unwrap_err().kind() {
+        ErrorKind::UnexpectedRoot(ref expected, ref found) => {
+            assert_eq!((expected.as_str(), found.as_str()), ("Point", "Wrong"))
+        },
+        ref other => panic!("unexpected error: {:?}", other),
+    }
+
+    let mut de = Deserializer::new_from_reader("<Point><x>1</x></Point>".as_bytes()).check_root_name(true);
+    assert_eq!(Point::deserialize(&mut de).unwrap(), Point { x: 1 });
+
+    let mut de = Deserializer::new_from_reader("<pt><x>1</x></pt>".as_bytes()).check_root_name(true);
+    assert_eq!(Renamed::deserialize(&mut de).unwrap(), Renamed { x: 1 });
+}
+
+#[test]
+fn borrowed_strings_are_owned() {
+    init_logger();
+
+    #[derive(Debug, Deserialize)]
+    struct Note<'a> {
+        #[serde(borrow)]
+        text: Cow<'a, str>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Borrowed<'a> {
+        #[allow(dead_code)]
+        text: &'a str,
+    }
+
+    let s = "<note><text>hello</text></note>";
+    let note: Note = from_str(s).unwrap();
+    assert!(matches!(note.text, Cow::Owned(ref text) if text == "hello"));
+
+    let err = from_str::<Borrowed>(s).unwrap_err();
+    assert!(err.to_string().contains("expected a borrowed string"), "{}", err);
+}
+
+#[test]
+fn repeated_siblings() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Root {
+        name: String,
+        #[serde(default)]
+        item: Vec<u32>,
+    }
+
+    let inputs = vec![
+        ("<root><name>none</name></root>", vec![]),
+        ("<root><name>one</name><item>1</item></root>", vec![1]),
+        ("<root><item>1</item><item>2</item><item>3</item><name>three</name></root>", vec![1, 2, 3]),
+    ];
+    for (s, items) in inputs {
+        assert_eq!(from_str::<Root>(s).unwrap().item, items);
+    }
+
+    // Repetitions have to be next to each other to form one sequence.
+    let s = "<root><item>1</item><name>split</name><item>2</item></root>";
+    assert!(from_str::<Root>(s).is_err());
+}
+
+#[test]
+fn qualified_names() {
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        #[serde(rename = "{http://a.example}id")]
+        a_id: u32,
+        #[serde(rename = "{http://b.example}id")]
+        b_id: u32,
+        #[serde(rename = "{http://b.example}kind")]
+        kind: String,
+        name: String,
+        #[serde(rename = "{http://b.example}tag", default)]
+        tags: Vec<String>,
+    }
+
+    let s = r##"
+        <record xmlns:a="http://a.example" xmlns:b="http://b.example" b:kind="test">
+            <a:id>1</a:id>
+            <b:id>2</b:id>
+            <name>plain</name>
+            <b:tag>x</b:tag>
+            <b:tag>y</b:tag>
+        </record>
+    "##;
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).qualified_names(true);
+    let record = Record::deserialize(&mut de).unwrap();
+    assert_eq!(
+        record,
+        Record {
+            a_id: 1,
+            b_id: 2,
+            kind: "test".to_string(),
+            name: "plain".to_string(),
+            tags: vec!["x".to_string(), "y".to_string()],
+        }
+    );
+
+    // Without the option, both elements are a plain `id`.
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Plain {
+        id: Vec<u32>,
+    }
+    let s = r#"<record xmlns:a="http://a.example" xmlns:b="http://b.example"><a:id>1</a:id><b:id>2</b:id></record>"#;
+    assert_eq!(from_str::<Plain>(s).unwrap(), Plain { id: vec![1, 2] });
+}
+
+#[test]
+fn unexpected_eof() {
+    init_logger();
+
+    #[derive(Debug, Deserialize)]
+    struct Post {
+        title: String,
+    }
+
+    let post = from_str::<Post>("<Post><title>Hi</title></Post>").unwrap();
+    assert_eq!(post.title, "Hi");
+
+    for s in &["", "<Post", "<Post><title>Hi", "<Post><title>Hi</title>"] {
+        let err = from_str::<Post>(s).unwrap_err();
+        assert!(matches!(err, Error(ErrorKind::UnexpectedEof(_), _)), "{:?}: {}", s, err);
+    }
+
+    let err = from_str::<Post>("<Post><title>Hi").unwrap_err();
+    assert_eq!(err.position().map(|position| position.column), Some(15));
+
+    // Malformed input is still a syntax error, however short.
+    let err = from_str::<Post>("<Post><title>Hi</x>").unwrap_err();
+    assert!(matches!(err, Error(ErrorKind::Syntax(_), _)), "{}", err);
+}